@@ -1,516 +1,522 @@
-#[derive(Clone, Copy, Debug)]
-enum Method {
-    Delete,
-    Get,
-    Post,
-    Put,
+use crate::config::Scope;
+use crate::rate_limit::Group;
+use crate::Endpoint;
+
+#[derive(Clone, Debug)]
+pub struct Api {
+    base_url: String,
+    transport: std::sync::Arc<dyn crate::transport::Transport>,
+    retry_policy: crate::RetryPolicy,
+    rate_limiter: Option<std::sync::Arc<crate::RateLimiter>>,
+    interceptors: crate::interceptor::Interceptors,
+    redactor: crate::Redactor,
 }
 
-#[derive(Clone, Debug, Default)]
-pub struct Api {}
-
-macro_rules! url {
+macro_rules! path {
     ($path:literal) => {
-        concat!("https://api.sumup.com", $path)
+        $path.to_string()
     };
     ($path:literal, $( $param:expr ),+ ) => {
-        &vec![
-            url!($path).to_string(),
+        vec![
+            path!($path),
             $( $param.to_string() ),+
         ].join("/")
     };
 }
 
-impl Api {
-    pub fn new() -> Self {
-        Self::default()
+impl Default for Api {
+    fn default() -> Self {
+        Self::new(crate::config::Environment::default().url())
     }
+}
 
-    pub fn token(&self, payload: impl serde::Serialize) -> crate::Result<crate::AccessToken> {
-        Self::send(Method::Post, url!("/token"), Some(payload), None)
+impl Api {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            transport: std::sync::Arc::new(crate::transport::Ureq::default()),
+            retry_policy: crate::RetryPolicy::default(),
+            rate_limiter: None,
+            interceptors: Vec::new(),
+            redactor: crate::Redactor::default(),
+        }
     }
 
-    pub fn account_get(&self, access_token: &crate::AccessToken) -> crate::Result<crate::Account> {
-        Self::send(
-            Method::Get,
-            url!("/v0.1/me"),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+    /**
+     * Creates an API for `config.environment`, whose transport follows `config.http`.
+     */
+    pub fn from_config(config: &crate::Config) -> crate::Result<Self> {
+        let transport = crate::transport::Ureq::with_config(&config.http)?;
 
-    pub fn checkout_create(
-        &self,
-        payload: impl serde::Serialize,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result {
-        Self::send(
-            Method::Post,
-            url!("/checkouts"),
-            Some(payload),
-            Some(access_token),
-        )
+        Ok(Self::new(config.environment.url()).with_transport(transport))
     }
 
-    pub fn checkout_list(
-        &self,
-        checkout_reference: &str,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<Vec<crate::Checkout>> {
-        Self::send(
-            Method::Get,
-            &format!(
-                "{}?checkout_reference={checkout_reference}",
-                url!("/v0.1/checkouts")
-            ),
-            None::<()>,
-            Some(access_token),
-        )
+    /**
+     * Replaces the default [`crate::transport::Ureq`] transport.
+     */
+    pub fn with_transport(mut self, transport: impl crate::transport::Transport + 'static) -> Self {
+        self.transport = std::sync::Arc::new(transport);
+        self
     }
 
-    pub fn checkout_get(
-        &self,
-        id: &str,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::Checkout> {
-        Self::send(
-            Method::Get,
-            url!("/checkouts", id),
-            None::<()>,
-            Some(access_token),
-        )
+    /**
+     * Sets the retry policy of idempotent requests.
+     */
+    pub fn with_retry_policy(mut self, retry_policy: crate::RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
     }
 
-    pub fn checkout_reference_id(
-        &self,
-        reference_id: &str,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::Checkout> {
-        Self::send(
-            Method::Get,
-            &format!("{}?checkout_reference={}", url!("/checkouts"), reference_id),
-            None::<()>,
-            Some(access_token),
-        )
+    /**
+     * Throttles requests, sharing `rate_limiter` with every clone of this API.
+     */
+    pub fn with_rate_limiter(mut self, rate_limiter: crate::RateLimiter) -> Self {
+        self.rate_limiter = Some(std::sync::Arc::new(rate_limiter));
+        self
     }
 
-    pub fn checkout_delete(&self, id: &str, access_token: &crate::AccessToken) -> crate::Result {
-        Self::send(
-            Method::Delete,
-            url!("/checkouts", id),
-            None::<()>,
-            Some(access_token),
-        )
+    /**
+     * Appends `interceptor` to the hooks called around each request.
+     */
+    pub fn with_interceptor(mut self, interceptor: impl crate::Interceptor + 'static) -> Self {
+        self.interceptors.push(std::sync::Arc::new(interceptor));
+        self
     }
 
-    pub fn checkout_update(
-        &self,
-        id: &str,
-        payload: impl serde::Serialize,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result {
-        Self::send(
-            Method::Put,
-            url!("/checkouts", id),
-            Some(payload),
-            Some(access_token),
-        )
+    /**
+     * Replaces the default [`crate::Redactor`] masking secrets in trace logs.
+     */
+    pub fn with_redactor(mut self, redactor: crate::Redactor) -> Self {
+        self.redactor = redactor;
+        self
     }
 
-    pub fn customer_create(
-        &self,
-        payload: impl serde::Serialize,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result {
-        Self::send(
-            Method::Post,
-            url!("/v0.1/customers"),
-            Some(payload),
-            Some(access_token),
-        )
+    pub fn base_url(&self) -> &str {
+        &self.base_url
     }
 
-    pub fn customer_update(
+    pub(crate) fn execute<T>(
         &self,
-        id: &str,
-        payload: impl serde::Serialize,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::Customer> {
-        Self::send(
-            Method::Put,
-            url!("/v0.1/customers", id),
-            Some(payload),
-            Some(access_token),
-        )
+        endpoint: Endpoint<T>,
+        access_token: Option<&crate::AccessToken>,
+    ) -> crate::Result<T> {
+        self.execute_with(endpoint, access_token, |_| Ok(None))
     }
 
-    pub fn customer_get(
+    /**
+     * Executes `endpoint`, replaying it once with the token returned by `renew` if the API
+     * answers `401 Unauthorized`.
+     */
+    pub(crate) fn execute_with<T>(
         &self,
-        id: &str,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::Customer> {
-        Self::send(
-            Method::Get,
-            url!("/v0.1/customers", id),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+        endpoint: Endpoint<T>,
+        access_token: Option<&crate::AccessToken>,
+        renew: impl FnOnce(crate::AccessToken) -> crate::Result<Option<crate::AccessToken>>,
+    ) -> crate::Result<T> {
+        let retry_policy = endpoint.retry_policy(&self.retry_policy);
+        let group = endpoint.group;
+        let call = crate::telemetry::Call::new(endpoint.method, &endpoint.path);
+        let (mut request, decoder) = endpoint.into_request(&self.base_url, access_token)?;
+        let mut attempts = 0;
+        let (mut sent, mut result) = call.in_scope(|| {
+            self.dispatch(request.clone(), retry_policy.clone(), group, &mut attempts)
+        })?;
+
+        if let Some(access_token) = rejected(&result, access_token) {
+            match renew(access_token.clone()) {
+                Ok(Some(access_token)) => {
+                    replay(&mut request, &access_token);
+                    (sent, result) = call
+                        .in_scope(|| self.dispatch(request, retry_policy, group, &mut attempts))?;
+                }
+                Ok(None) => (),
+                Err(error) => result = Err(error),
+            }
+        }
 
-    pub fn customer_payment_instruments(
-        &self,
-        customer_id: &str,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<Vec<crate::Card>> {
-        Self::send(
-            Method::Get,
-            url!("/v0.1/customers", customer_id, "payment-instruments"),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+        call.finish(attempts, &result);
 
-    pub fn customer_create_payment_instruments(
-        &self,
-        customer_id: &str,
-        payload: impl serde::Serialize,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::Card> {
-        Self::send(
-            Method::Post,
-            url!("/v0.1/customers", customer_id, "payment-instruments"),
-            Some(payload),
-            Some(access_token),
-        )
+        decoder(&sent, result?)
     }
 
-    pub fn customer_delete_payment_instruments(
+    /**
+     * Sends `request` until it succeeds or `retry_policy` gives up, returning the last request
+     * sent with its result and counting each try in `attempts`.
+     */
+    fn dispatch(
         &self,
-        customer_id: &str,
-        card_token: &str,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result {
-        Self::send(
-            Method::Delete,
-            url!("/customers", customer_id, "payment-instruments", card_token),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+        request: crate::transport::Request,
+        retry_policy: crate::RetryPolicy,
+        group: Option<Group>,
+        attempts: &mut u32,
+    ) -> crate::Result<(
+        crate::transport::Request,
+        crate::Result<crate::transport::Response>,
+    )> {
+        let mut attempt = 1;
+
+        loop {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                let delay = rate_limiter.reserve(group);
+
+                if !delay.is_zero() {
+                    log::debug!(
+                        "Throttling {} {} for {delay:?}",
+                        request.method,
+                        request.url
+                    );
+                    std::thread::sleep(delay);
+                }
+            }
 
-    pub fn merchants_payment_methods(
-        &self,
-        merchant_code: &str,
-        amount: Option<f32>,
-        currency: Option<&str>,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<Vec<crate::PaymentMethod>> {
-        let mut url = url!("/v0.1/merchants", merchant_code, "payment-methods?").to_string();
-
-        if let Some(amount) = amount {
-            url.push_str(&format!("amount={amount}&"));
-        }
+            let sent = crate::interceptor::request(&self.interceptors, request.clone())?;
+            self.redactor.log_request(&sent);
+            let start = std::time::Instant::now();
+            let result = self.transport.send(sent.clone());
+            let result =
+                crate::interceptor::response(&self.interceptors, &sent, result, start.elapsed());
 
-        if let Some(currency) = currency {
-            url.push_str(&format!("currency={currency}"));
-        }
+            if let Ok(response) = &result {
+                self.redactor.log_response(response);
+            }
 
-        Self::send(Method::Get, &url, None::<()>, Some(access_token))
+            *attempts += 1;
+
+            match retry_policy.delay(attempt, &result) {
+                Some(delay) => {
+                    log::debug!(
+                        "Retrying {} {} in {delay:?} after attempt {attempt}",
+                        request.method,
+                        request.url
+                    );
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+                None => return Ok((sent, result)),
+            }
+        }
     }
+}
 
-    pub fn personal_get(
-        &self,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::PersonalProfile> {
-        Self::send(
-            Method::Get,
-            url!("/v0.1/me/personal-profile"),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+impl crate::Client for Api {
+    type Output<'a, T: 'a> = crate::Result<T>;
 
-    pub fn profile_get(&self, access_token: &crate::AccessToken) -> crate::Result<crate::Profile> {
-        Self::send(
-            Method::Get,
-            url!("/v0.1/me/merchant-profile"),
-            None::<()>,
-            Some(access_token),
-        )
+    fn send<T: Send + 'static>(&self, endpoint: Endpoint<T>) -> Self::Output<'_, T> {
+        self.execute(endpoint, None)
     }
 
-    pub fn profile_update(
-        &self,
-        profile: &crate::Profile,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result {
-        Self::send(
-            Method::Put,
-            url!("/v0.1/me/merchant-profile"),
-            Some(profile),
-            Some(access_token),
-        )
+    fn base_url(&self) -> &str {
+        &self.base_url
     }
+}
 
-    pub fn profile_doing_business_as_get(
-        &self,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::DoingBusinessAs> {
-        Self::send(
-            Method::Get,
-            url!("/v0.1/me/merchant-profile/doing-business-as"),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+/**
+ * Returns the access token a request was sent with if it was answered `401 Unauthorized`, to
+ * renew it before replaying the request.
+ */
+pub(crate) fn rejected<'a>(
+    result: &crate::Result<crate::transport::Response>,
+    access_token: Option<&'a crate::AccessToken>,
+) -> Option<&'a crate::AccessToken> {
+    access_token.filter(|_| matches!(result, Ok(response) if response.status == 401))
+}
 
-    pub fn profile_doing_business_as_update(
-        &self,
-        dba: &crate::DoingBusinessAs,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::DoingBusinessAs> {
-        Self::send(
-            Method::Put,
-            url!("/v0.1/me/merchant-profile/doing-business-as"),
-            Some(dba),
-            Some(access_token),
-        )
-    }
+/**
+ * Authenticates `request` with the renewed `access_token`, to replay it.
+ */
+pub(crate) fn replay(request: &mut crate::transport::Request, access_token: &crate::AccessToken) {
+    log::debug!(
+        "Replaying {} {} with a renewed access token",
+        request.method,
+        request.url
+    );
+    request.set_header("Authorization", access_token.bearer());
+}
 
-    pub fn profile_bank_accounts(
-        &self,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<Vec<crate::BankAccount>> {
-        Self::send(
-            Method::Get,
-            url!("/v0.1/me/merchant-profile/bank-accounts"),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+pub(crate) fn token(payload: impl serde::Serialize) -> Endpoint<crate::AccessToken> {
+    Endpoint::post("/token", payload).with_group(Group::Authorization)
+}
 
-    pub fn profile_settings(
-        &self,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::Settings> {
-        Self::send(
-            Method::Get,
-            url!("/v0.1/me/merchant-profile/settings"),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+pub(crate) fn revoke(payload: impl serde::Serialize) -> Endpoint {
+    Endpoint::<()>::post("/revoke", payload)
+        .with_idempotent(true)
+        .with_group(Group::Authorization)
+        .with_decoder(|_, response| response.check())
+}
 
-    pub fn payouts_list(
-        &self,
-        filter: &crate::services::payouts::Filter,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<Vec<crate::Payout>> {
-        Self::send(
-            Method::Get,
-            &format!(
-                "{}?{}",
-                url!("/v0.1/me/financials/payouts"),
-                filter.to_string()
-            ),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+pub(crate) fn account_get() -> Endpoint<crate::Account> {
+    Endpoint::get("/v0.1/me")
+        .with_group(Group::Account)
+        .with_scopes(&[Scope::UserProfileReadonly])
+}
 
-    pub fn subaccounts_create(
-        &self,
-        payload: impl serde::Serialize,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::SubAccount> {
-        Self::send(
-            Method::Post,
-            url!("/v0.1/me/accounts"),
-            Some(payload),
-            Some(access_token),
-        )
-    }
+pub(crate) fn checkout_create(payload: impl serde::Serialize) -> Endpoint<crate::IdempotencyKey> {
+    Endpoint::<()>::post("/checkouts", payload)
+        .with_idempotency_key_output()
+        .with_group(Group::Checkouts)
+        .with_scopes(&[Scope::Payments])
+}
 
-    pub fn subaccounts_delete(
-        &self,
-        id: &str,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::SubAccount> {
-        Self::send(
-            Method::Delete,
-            url!("/v0.1/me/accounts", id),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+pub(crate) fn checkout_list(checkout_reference: &str) -> Endpoint<Vec<crate::Checkout>> {
+    Endpoint::get(&format!(
+        "{}?checkout_reference={checkout_reference}",
+        path!("/v0.1/checkouts")
+    ))
+    .with_group(Group::Checkouts)
+    .with_scopes(&[Scope::Payments])
+}
 
-    pub fn subaccounts_list(
-        &self,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<Vec<crate::SubAccount>> {
-        Self::send(
-            Method::Get,
-            url!("/v0.1/me/accounts"),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+pub(crate) fn checkout_get(id: &str) -> Endpoint<crate::Checkout> {
+    Endpoint::get(&path!("/checkouts", id))
+        .with_group(Group::Checkouts)
+        .with_scopes(&[Scope::Payments])
+}
 
-    pub fn subaccounts_update(
-        &self,
-        id: &str,
-        payload: impl serde::Serialize,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::SubAccount> {
-        Self::send(
-            Method::Put,
-            url!("/v0.1/me/accounts", id),
-            Some(payload),
-            Some(access_token),
-        )
-    }
+pub(crate) fn checkout_reference_id(reference_id: &str) -> Endpoint<crate::Checkout> {
+    Endpoint::get(&format!(
+        "{}?checkout_reference={reference_id}",
+        path!("/checkouts")
+    ))
+    .with_group(Group::Checkouts)
+    .with_scopes(&[Scope::Payments])
+}
 
-    pub fn transactions_list(
-        &self,
-        filter: &crate::services::payouts::Filter,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<Vec<crate::Transaction>> {
-        Self::send(
-            Method::Get,
-            &format!(
-                "{}?{}",
-                url!("/v0.1/me/financials/transactions"),
-                filter.to_string()
-            ),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+pub(crate) fn checkout_delete(id: &str) -> Endpoint {
+    Endpoint::delete(&path!("/checkouts", id))
+        .with_group(Group::Checkouts)
+        .with_scopes(&[Scope::Payments])
+}
 
-    pub fn transactions_get(
-        &self,
-        id: &str,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::Transaction> {
-        Self::send(
-            Method::Get,
-            &format!("{}?id={id}", url!("/v0.1/me/transactions")),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+pub(crate) fn checkout_update(
+    id: &str,
+    payload: impl serde::Serialize,
+) -> Endpoint<crate::IdempotencyKey> {
+    // Processing a checkout charges the customer
+    Endpoint::<()>::put(&path!("/checkouts", id), payload)
+        .with_idempotent(false)
+        .with_idempotency_key_output()
+        .with_group(Group::Checkouts)
+        .with_scopes(&[Scope::Payments])
+}
 
-    pub fn transactions_get_by_internal_id(
-        &self,
-        internal_id: &str,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::Transaction> {
-        Self::send(
-            Method::Get,
-            &format!(
-                "{}?internal_id={internal_id}",
-                url!("/v0.1/me/transactions")
-            ),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+pub(crate) fn customer_create(payload: impl serde::Serialize) -> Endpoint<crate::IdempotencyKey> {
+    Endpoint::<()>::post("/v0.1/customers", payload)
+        .with_idempotency_key_output()
+        .with_group(Group::Customers)
+        .with_scopes(&[Scope::PaymentInstruments])
+}
 
-    pub fn transactions_get_by_code(
-        &self,
-        transaction_code: &str,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::Transaction> {
-        Self::send(
-            Method::Get,
-            &format!(
-                "{}?transaction_code={transaction_code}",
-                url!("/v0.1/me/transactions")
-            ),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+pub(crate) fn customer_update(
+    id: &str,
+    payload: impl serde::Serialize,
+) -> Endpoint<crate::Customer> {
+    Endpoint::put(&path!("/v0.1/customers", id), payload)
+        .with_group(Group::Customers)
+        .with_scopes(&[Scope::PaymentInstruments])
+}
 
-    pub fn transactions_history(
-        &self,
-        filter: &crate::services::transactions::Filter,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<Vec<crate::Transaction>> {
-        Self::send(
-            Method::Get,
-            &format!(
-                "{}?{}",
-                url!("/v0.1/me/financials/payouts"),
-                filter.to_string()
-            ),
-            None::<()>,
-            Some(access_token),
-        )
-    }
+pub(crate) fn customer_get(id: &str) -> Endpoint<crate::Customer> {
+    Endpoint::get(&path!("/v0.1/customers", id))
+        .with_group(Group::Customers)
+        .with_scopes(&[Scope::PaymentInstruments])
+}
 
-    pub fn transactions_refund(
-        &self,
-        id: u32,
-        payload: impl serde::Serialize,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result {
-        Self::send(
-            Method::Get,
-            url!("/v0.1/me/refund", id),
-            Some(payload),
-            Some(access_token),
-        )
+pub(crate) fn customer_payment_instruments(customer_id: &str) -> Endpoint<Vec<crate::Card>> {
+    Endpoint::get(&path!(
+        "/v0.1/customers",
+        customer_id,
+        "payment-instruments"
+    ))
+    .with_group(Group::Customers)
+    .with_scopes(&[Scope::PaymentInstruments])
+}
+
+pub(crate) fn customer_create_payment_instruments(
+    customer_id: &str,
+    payload: impl serde::Serialize,
+) -> Endpoint<crate::Card> {
+    Endpoint::post(
+        &path!("/v0.1/customers", customer_id, "payment-instruments"),
+        payload,
+    )
+    .with_group(Group::Customers)
+    .with_scopes(&[Scope::PaymentInstruments])
+}
+
+pub(crate) fn customer_delete_payment_instruments(customer_id: &str, card_token: &str) -> Endpoint {
+    Endpoint::delete(&path!(
+        "/customers",
+        customer_id,
+        "payment-instruments",
+        card_token
+    ))
+    .with_group(Group::Customers)
+    .with_scopes(&[Scope::PaymentInstruments])
+}
+
+pub(crate) fn merchants_payment_methods(
+    merchant_code: &str,
+    amount: Option<f32>,
+    currency: Option<&str>,
+) -> Endpoint<Vec<crate::PaymentMethod>> {
+    let mut path = path!("/v0.1/merchants", merchant_code, "payment-methods?");
+
+    if let Some(amount) = amount {
+        path.push_str(&format!("amount={amount}&"));
     }
 
-    pub fn transactions_get_receipt(
-        &self,
-        id: u32,
-        merchant_id: u32,
-        access_token: &crate::AccessToken,
-    ) -> crate::Result<crate::Receipt> {
-        Self::send(
-            Method::Get,
-            &format!("{}?mid={merchant_id}", url!("/receipts", id)),
-            None::<()>,
-            Some(access_token),
-        )
+    if let Some(currency) = currency {
+        path.push_str(&format!("currency={currency}"));
     }
 
-    fn send<T: serde::de::DeserializeOwned>(
-        method: Method,
-        url: &str,
-        payload: Option<impl serde::Serialize>,
-        access_token: Option<&crate::AccessToken>,
-    ) -> crate::Result<T> {
-        log::trace!("-> {method:?} {url}");
-
-        let mut request = match method {
-            Method::Get => ureq::get(url),
-            Method::Post => ureq::post(url),
-            Method::Delete => ureq::delete(url),
-            Method::Put => ureq::delete(url),
-        };
-
-        if let Some(access_token) = access_token {
-            let bearer = access_token.bearer();
-            log::trace!("-> Authorization: {bearer}");
-            request = request.set("Authorization", &bearer);
-        }
+    Endpoint::get(&path)
+        .with_group(Group::Checkouts)
+        .with_scopes(&[Scope::Payments])
+}
 
-        let response = match payload {
-            Some(payload) => {
-                if log::log_enabled!(log::Level::Trace) {
-                    log::trace!("-> {}", serde_json::to_string(&payload)?);
-                }
-                request.send_json(payload)?
-            }
-            None => request.call()?,
-        };
+pub(crate) fn personal_get() -> Endpoint<crate::PersonalProfile> {
+    Endpoint::get("/v0.1/me/personal-profile")
+        .with_group(Group::Account)
+        .with_scopes(&[Scope::UserProfileReadonly])
+}
 
-        let content = response.into_string()?;
-        log::trace!("<- {content}");
+pub(crate) fn profile_get() -> Endpoint<crate::Profile> {
+    Endpoint::get("/v0.1/me/merchant-profile")
+        .with_group(Group::Merchant)
+        .with_scopes(&[Scope::UserProfileReadonly])
+}
 
-        serde_json::from_str(&content).map_err(crate::Error::from)
-    }
+pub(crate) fn profile_update(profile: &crate::Profile) -> Endpoint {
+    Endpoint::put("/v0.1/me/merchant-profile", profile)
+        .with_group(Group::Merchant)
+        .with_scopes(&[Scope::UserProfile])
+}
+
+pub(crate) fn profile_doing_business_as_get() -> Endpoint<crate::DoingBusinessAs> {
+    Endpoint::get("/v0.1/me/merchant-profile/doing-business-as")
+        .with_group(Group::Merchant)
+        .with_scopes(&[Scope::UserProfileReadonly])
+}
+
+pub(crate) fn profile_doing_business_as_update(
+    dba: &crate::DoingBusinessAs,
+) -> Endpoint<crate::DoingBusinessAs> {
+    Endpoint::put("/v0.1/me/merchant-profile/doing-business-as", dba)
+        .with_group(Group::Merchant)
+        .with_scopes(&[Scope::UserProfile])
+}
+
+pub(crate) fn profile_bank_accounts() -> Endpoint<Vec<crate::BankAccount>> {
+    Endpoint::get("/v0.1/me/merchant-profile/bank-accounts")
+        .with_group(Group::Merchant)
+        .with_scopes(&[Scope::UserProfileReadonly])
+}
+
+pub(crate) fn profile_settings() -> Endpoint<crate::Settings> {
+    Endpoint::get("/v0.1/me/merchant-profile/settings")
+        .with_group(Group::Merchant)
+        .with_scopes(&[Scope::UserAppSettings])
+}
+
+pub(crate) fn payouts_list(
+    filter: &crate::services::payouts::Filter,
+) -> Endpoint<Vec<crate::Payout>> {
+    Endpoint::get(&format!(
+        "{}?{}",
+        path!("/v0.1/me/financials/payouts"),
+        filter
+    ))
+    .with_group(Group::Payouts)
+    .with_scopes(&[Scope::TransactionsHistory])
+}
+
+pub(crate) fn subaccounts_create(payload: impl serde::Serialize) -> Endpoint<crate::SubAccount> {
+    Endpoint::post("/v0.1/me/accounts", payload)
+        .with_group(Group::Subaccounts)
+        .with_scopes(&[Scope::UserSubaccounts])
+}
+
+pub(crate) fn subaccounts_delete(id: &str) -> Endpoint<crate::SubAccount> {
+    Endpoint::delete(&path!("/v0.1/me/accounts", id))
+        .with_group(Group::Subaccounts)
+        .with_scopes(&[Scope::UserSubaccounts])
+}
+
+pub(crate) fn subaccounts_list() -> Endpoint<Vec<crate::SubAccount>> {
+    Endpoint::get("/v0.1/me/accounts")
+        .with_group(Group::Subaccounts)
+        .with_scopes(&[Scope::UserSubaccounts])
+}
+
+pub(crate) fn subaccounts_update(
+    id: &str,
+    payload: impl serde::Serialize,
+) -> Endpoint<crate::SubAccount> {
+    Endpoint::put(&path!("/v0.1/me/accounts", id), payload)
+        .with_group(Group::Subaccounts)
+        .with_scopes(&[Scope::UserSubaccounts])
+}
+
+pub(crate) fn transactions_list(
+    filter: &crate::services::payouts::Filter,
+) -> Endpoint<Vec<crate::Transaction>> {
+    Endpoint::get(&format!(
+        "{}?{}",
+        path!("/v0.1/me/financials/transactions"),
+        filter
+    ))
+    .with_group(Group::Payouts)
+    .with_scopes(&[Scope::TransactionsHistory])
+}
+
+pub(crate) fn transactions_get(id: &str) -> Endpoint<crate::Transaction> {
+    Endpoint::get(&format!("{}?id={id}", path!("/v0.1/me/transactions")))
+        .with_group(Group::Transactions)
+        .with_scopes(&[Scope::TransactionsHistory])
+}
+
+pub(crate) fn transactions_get_by_internal_id(internal_id: &str) -> Endpoint<crate::Transaction> {
+    Endpoint::get(&format!(
+        "{}?internal_id={internal_id}",
+        path!("/v0.1/me/transactions")
+    ))
+    .with_group(Group::Transactions)
+    .with_scopes(&[Scope::TransactionsHistory])
+}
+
+pub(crate) fn transactions_get_by_code(transaction_code: &str) -> Endpoint<crate::Transaction> {
+    Endpoint::get(&format!(
+        "{}?transaction_code={transaction_code}",
+        path!("/v0.1/me/transactions")
+    ))
+    .with_group(Group::Transactions)
+    .with_scopes(&[Scope::TransactionsHistory])
+}
+
+pub(crate) fn transactions_history(
+    filter: &crate::services::transactions::Filter,
+) -> Endpoint<Vec<crate::Transaction>> {
+    Endpoint::get(&format!(
+        "{}?{}",
+        path!("/v0.1/me/financials/payouts"),
+        filter
+    ))
+    .with_group(Group::Transactions)
+    .with_scopes(&[Scope::TransactionsHistory])
+}
+
+pub(crate) fn transactions_refund(
+    id: u32,
+    payload: impl serde::Serialize,
+) -> Endpoint<crate::IdempotencyKey> {
+    Endpoint::<()>::post(&path!("/v0.1/me/refund", id), payload)
+        .with_idempotency_key_output()
+        .with_group(Group::Transactions)
+        .with_scopes(&[Scope::Payments])
+}
+
+pub(crate) fn transactions_get_receipt(id: u32, merchant_id: u32) -> Endpoint<crate::Receipt> {
+    Endpoint::get(&format!("{}?mid={merchant_id}", path!("/receipts", id)))
+        .with_group(Group::Transactions)
+        .with_scopes(&[Scope::TransactionsHistory])
 }
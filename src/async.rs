@@ -0,0 +1,756 @@
+/*!
+ * Non-blocking client, available with the `async` feature.
+ *
+ * It shares the entities, errors and services of the blocking client: services returned by
+ * [`SumUp`] return futures instead of results.
+ */
+
+use crate::transport::{Method, Request, Response};
+use crate::Endpoint;
+
+type Future<'a, T> =
+    std::pin::Pin<Box<dyn std::future::Future<Output = crate::Result<T>> + Send + 'a>>;
+
+pub mod services {
+    pub type Account<'a> = crate::services::Account<'a, super::SumUp>;
+    pub type Authorization<'a> = crate::services::Authorization<'a, super::Api>;
+    pub type Checkout<'a> = crate::services::Checkout<'a, super::SumUp>;
+    pub type Customer<'a> = crate::services::Customer<'a, super::SumUp>;
+    pub type Merchant<'a> = crate::services::Merchant<'a, super::SumUp>;
+    pub type Payouts<'a> = crate::services::Payouts<'a, super::SumUp>;
+    pub type Personal<'a> = crate::services::Personal<'a, super::SumUp>;
+    pub type Subaccounts<'a> = crate::services::Subaccounts<'a, super::SumUp>;
+    pub type Transactions<'a> = crate::services::Transactions<'a, super::SumUp>;
+}
+
+/**
+ * Non-blocking version of [`crate::transport::Transport`].
+ */
+pub trait Transport: std::fmt::Debug + Send + Sync {
+    /**
+     * Sends `request` and returns the response, whatever its status.
+     */
+    fn send(&self, request: Request) -> Future<'_, Response>;
+}
+
+impl<T: Transport + ?Sized> Transport for std::sync::Arc<T> {
+    fn send(&self, request: Request) -> Future<'_, Response> {
+        (**self).send(request)
+    }
+}
+
+impl Transport for crate::transport::Memory {
+    fn send(&self, request: Request) -> Future<'_, Response> {
+        let response = crate::transport::Transport::send(self, request);
+
+        Box::pin(std::future::ready(response))
+    }
+}
+
+fn transport(error: reqwest::Error) -> crate::Error {
+    crate::Error::Transport(Box::new(error))
+}
+
+/**
+ * The default non-blocking transport, based on [`reqwest`].
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Reqwest {
+    client: reqwest::Client,
+}
+
+impl Reqwest {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    /**
+     * Builds a client, whose connections are reused by every request, according to `http`.
+     */
+    pub fn with_config(http: &crate::config::Http) -> crate::Result<Self> {
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(timeout) = http.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        if let Some(timeout) = http.read_timeout {
+            builder = builder.read_timeout(timeout);
+        }
+
+        if let Some(timeout) = http.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        if let Some(proxy) = &http.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|error| crate::Error::InvalidConfig(format!("proxy: {error}")))?;
+            builder = builder.proxy(proxy);
+        }
+
+        let invalid = |error: &dyn std::fmt::Display| {
+            crate::Error::InvalidConfig(format!("root certificates: {error}"))
+        };
+
+        for pem in &http.root_certificates {
+            let certificates = reqwest::Certificate::from_pem_bundle(pem.as_bytes())
+                .map_err(|error| invalid(&error))?;
+
+            if certificates.is_empty() {
+                return Err(invalid(&"no PEM certificate found"));
+            }
+
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        let client = builder
+            .build()
+            .map_err(|error| crate::Error::InvalidConfig(format!("HTTP client: {error}")))?;
+
+        Ok(Self::new(client))
+    }
+}
+
+impl Transport for Reqwest {
+    fn send(&self, request: Request) -> Future<'_, Response> {
+        Box::pin(async move {
+            let method = match request.method {
+                Method::Get => reqwest::Method::GET,
+                Method::Post => reqwest::Method::POST,
+                Method::Delete => reqwest::Method::DELETE,
+                Method::Put => reqwest::Method::PUT,
+            };
+
+            let mut call = self.client.request(method, &request.url);
+
+            for (name, value) in &request.headers {
+                call = call.header(name, value);
+            }
+
+            if let Some(body) = request.body {
+                call = call.body(body);
+            }
+
+            let response = call.send().await.map_err(transport)?;
+
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    let value = value.to_str().ok()?.to_string();
+                    Some((name.to_string(), value))
+                })
+                .collect();
+
+            Ok(Response {
+                status,
+                headers,
+                body: response.text().await.map_err(transport)?,
+            })
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Api {
+    base_url: String,
+    transport: std::sync::Arc<dyn Transport>,
+    retry_policy: crate::RetryPolicy,
+    rate_limiter: Option<std::sync::Arc<crate::RateLimiter>>,
+    interceptors: crate::interceptor::Interceptors,
+    redactor: crate::Redactor,
+}
+
+impl Default for Api {
+    fn default() -> Self {
+        Self::new(crate::config::Environment::default().url())
+    }
+}
+
+impl Api {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            transport: std::sync::Arc::new(Reqwest::default()),
+            retry_policy: crate::RetryPolicy::default(),
+            rate_limiter: None,
+            interceptors: Vec::new(),
+            redactor: crate::Redactor::default(),
+        }
+    }
+
+    /**
+     * Creates an API for `config.environment`, whose transport follows `config.http`.
+     */
+    pub fn from_config(config: &crate::Config) -> crate::Result<Self> {
+        let transport = Reqwest::with_config(&config.http)?;
+
+        Ok(Self::new(config.environment.url()).with_transport(transport))
+    }
+
+    /**
+     * Replaces the default [`Reqwest`] transport.
+     */
+    pub fn with_transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = std::sync::Arc::new(transport);
+        self
+    }
+
+    /**
+     * Sets the retry policy of idempotent requests.
+     */
+    pub fn with_retry_policy(mut self, retry_policy: crate::RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /**
+     * Throttles requests, sharing `rate_limiter` with every clone of this API.
+     */
+    pub fn with_rate_limiter(mut self, rate_limiter: crate::RateLimiter) -> Self {
+        self.rate_limiter = Some(std::sync::Arc::new(rate_limiter));
+        self
+    }
+
+    /**
+     * Appends `interceptor` to the hooks called around each request.
+     */
+    pub fn with_interceptor(mut self, interceptor: impl crate::Interceptor + 'static) -> Self {
+        self.interceptors.push(std::sync::Arc::new(interceptor));
+        self
+    }
+
+    /**
+     * Replaces the default [`crate::Redactor`] masking secrets in trace logs.
+     */
+    pub fn with_redactor(mut self, redactor: crate::Redactor) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub(crate) async fn execute<T>(
+        &self,
+        endpoint: Endpoint<T>,
+        access_token: Option<&crate::AccessToken>,
+    ) -> crate::Result<T> {
+        self.execute_with(endpoint, access_token, |_| async { Ok(None) })
+            .await
+    }
+
+    /**
+     * Executes `endpoint`, replaying it once with the token returned by `renew` if the API
+     * answers `401 Unauthorized`.
+     */
+    pub(crate) async fn execute_with<T, F>(
+        &self,
+        endpoint: Endpoint<T>,
+        access_token: Option<&crate::AccessToken>,
+        renew: impl FnOnce(crate::AccessToken) -> F,
+    ) -> crate::Result<T>
+    where
+        F: std::future::Future<Output = crate::Result<Option<crate::AccessToken>>>,
+    {
+        let retry_policy = endpoint.retry_policy(&self.retry_policy);
+        let group = endpoint.group;
+        let call = crate::telemetry::Call::new(endpoint.method, &endpoint.path);
+        let (mut request, decoder) = endpoint.into_request(&self.base_url, access_token)?;
+        let mut attempts = 0;
+        let (mut sent, mut result) = call
+            .instrument(self.dispatch(request.clone(), retry_policy.clone(), group, &mut attempts))
+            .await?;
+
+        if let Some(access_token) = crate::api::rejected(&result, access_token) {
+            match renew(access_token.clone()).await {
+                Ok(Some(access_token)) => {
+                    crate::api::replay(&mut request, &access_token);
+                    (sent, result) = call
+                        .instrument(self.dispatch(request, retry_policy, group, &mut attempts))
+                        .await?;
+                }
+                Ok(None) => (),
+                Err(error) => result = Err(error),
+            }
+        }
+
+        call.finish(attempts, &result);
+
+        decoder(&sent, result?)
+    }
+
+    /**
+     * Sends `request` until it succeeds or `retry_policy` gives up, returning the last request
+     * sent with its result and counting each try in `attempts`.
+     */
+    async fn dispatch(
+        &self,
+        request: Request,
+        retry_policy: crate::RetryPolicy,
+        group: Option<crate::rate_limit::Group>,
+        attempts: &mut u32,
+    ) -> crate::Result<(Request, crate::Result<Response>)> {
+        let mut attempt = 1;
+
+        loop {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                let delay = rate_limiter.reserve(group);
+
+                if !delay.is_zero() {
+                    log::debug!(
+                        "Throttling {} {} for {delay:?}",
+                        request.method,
+                        request.url
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+
+            let sent = crate::interceptor::request(&self.interceptors, request.clone())?;
+            self.redactor.log_request(&sent);
+            let start = std::time::Instant::now();
+            let result = self.transport.send(sent.clone()).await;
+            let result =
+                crate::interceptor::response(&self.interceptors, &sent, result, start.elapsed());
+
+            if let Ok(response) = &result {
+                self.redactor.log_response(response);
+            }
+
+            *attempts += 1;
+
+            match retry_policy.delay(attempt, &result) {
+                Some(delay) => {
+                    log::debug!(
+                        "Retrying {} {} in {delay:?} after attempt {attempt}",
+                        request.method,
+                        request.url
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                None => return Ok((sent, result)),
+            }
+        }
+    }
+}
+
+impl crate::Client for Api {
+    type Output<'a, T: 'a> = Future<'a, T>;
+
+    fn send<T: Send + 'static>(&self, endpoint: Endpoint<T>) -> Self::Output<'_, T> {
+        Box::pin(self.execute(endpoint, None))
+    }
+
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+}
+
+/**
+ * Non-blocking version of [`crate::SumUp`], also `Send + Sync` and sharing its access token with
+ * its clones, whose requests can be spawned as tasks.
+ */
+#[derive(Clone, Debug)]
+pub struct SumUp {
+    session: crate::session::Session,
+    /** Held while renewing the access token */
+    renewal: std::sync::Arc<tokio::sync::Mutex<()>>,
+    api: Api,
+    config: crate::Config,
+}
+
+impl SumUp {
+    pub async fn new(client_id: &str, client_secret: &str, code: &str) -> crate::Result<Self> {
+        let config = crate::Config::new(client_id, client_secret, code);
+
+        Self::from(config).await
+    }
+
+    pub async fn from(config: crate::Config) -> crate::Result<Self> {
+        let api = Api::from_config(&config)?;
+
+        Self::with_api(api, config).await
+    }
+
+    /**
+     * Creates a client sending its requests through `api`, whose base URL supersedes
+     * `config.environment`.
+     */
+    pub async fn with_api(api: Api, config: crate::Config) -> crate::Result<Self> {
+        let authorization = crate::services::Authorization::new(&api, &config);
+        let access_token = authorization.token().await?;
+
+        let sumup = Self {
+            session: crate::session::Session::new(access_token),
+            renewal: Default::default(),
+            api,
+            config,
+        };
+
+        Ok(sumup)
+    }
+
+    pub fn access_token(&self) -> crate::AccessToken {
+        self.session.access_token()
+    }
+
+    /**
+     * <https://developer.sumup.com/docs/api/generate-a-token/>
+     */
+    pub async fn refresh_token(&self, refresh_token: Option<&str>) -> crate::Result {
+        let _renewal = self.renewal.lock().await;
+        let access_token = self.access_token();
+        let refresh_token = crate::session::refresh_token(&access_token, refresh_token)?;
+        let token = self.authorization().refresh_token(refresh_token).await?;
+        self.session
+            .replace(&self.authorization(), &access_token, token)?;
+
+        Ok(())
+    }
+
+    /**
+     * Revokes the access and refresh tokens, then forgets them, removing them from the token
+     * store. Requests fail afterwards, except with an API key which can't be revoked.
+     *
+     * The tokens are forgotten even if revoking them fails, the first error being returned.
+     */
+    pub async fn logout(&self) -> crate::Result {
+        let _renewal = self.renewal.lock().await;
+        let mut revoked = Ok(());
+
+        for token in self.session.revocable(&self.config) {
+            revoked = revoked.and(self.authorization().revoke(token.expose()).await);
+        }
+
+        self.session
+            .logout(&self.authorization(), &self.config, revoked)
+    }
+
+    /**
+     * Returns the access token, renewed first if it is about to expire.
+     */
+    async fn valid_token(&self) -> crate::Result<crate::AccessToken> {
+        let access_token = self.session.current()?;
+
+        if access_token.expires_within(crate::EXPIRY_MARGIN) {
+            if let Some(access_token) = self.renew(access_token.clone()).await? {
+                return Ok(access_token);
+            }
+        }
+
+        Ok(access_token)
+    }
+
+    /**
+     * Replaces the `stale` access token, unless another call already did.
+     */
+    async fn renew(&self, stale: crate::AccessToken) -> crate::Result<Option<crate::AccessToken>> {
+        let _renewal = self.renewal.lock().await;
+
+        if let Some(access_token) = self.session.replacement(&stale) {
+            return Ok(Some(access_token));
+        }
+
+        let Some(token) = self.authorization().renew(&stale)? else {
+            return Ok(None);
+        };
+        let token = token.await?;
+        log::debug!("Renewed the access token");
+
+        self.session
+            .replace(&self.authorization(), &stale, token)
+            .map(Some)
+    }
+
+    /**
+     * <https://developer.sumup.com/docs/api/account-details/>
+     */
+    pub fn account(&self) -> services::Account<'_> {
+        crate::services::Account::new(self)
+    }
+
+    /**
+     * <https://developer.sumup.com/docs/api/authorization/>
+     */
+    pub fn authorization(&self) -> services::Authorization<'_> {
+        crate::services::Authorization::new(&self.api, &self.config)
+    }
+
+    /**
+     * <https://developer.sumup.com/docs/api/checkouts/>
+     */
+    pub fn checkout(&self) -> services::Checkout<'_> {
+        crate::services::Checkout::new(self)
+    }
+
+    /**
+     * <https://developer.sumup.com/docs/api/customers/>
+     */
+    pub fn customer(&self) -> services::Customer<'_> {
+        crate::services::Customer::new(self)
+    }
+
+    /**
+     * <https://developer.sumup.com/docs/api/merchant-account/>
+     */
+    pub fn merchant(&self) -> services::Merchant<'_> {
+        crate::services::Merchant::new(self)
+    }
+
+    pub fn payouts(&self) -> services::Payouts<'_> {
+        crate::services::Payouts::new(self)
+    }
+
+    /**
+     * <https://developer.sumup.com/docs/api/personal-account/>
+     */
+    pub fn personal(&self) -> services::Personal<'_> {
+        crate::services::Personal::new(self)
+    }
+
+    /**
+     * <https://developer.sumup.com/docs/api/subaccounts/>
+     */
+    pub fn subaccounts(&self) -> services::Subaccounts<'_> {
+        crate::services::Subaccounts::new(self)
+    }
+
+    /**
+     * <https://developer.sumup.com/docs/api/transactions/>
+     */
+    pub fn transactions(&self) -> services::Transactions<'_> {
+        crate::services::Transactions::new(self)
+    }
+}
+
+impl crate::Client for SumUp {
+    type Output<'a, T: 'a> = Future<'a, T>;
+
+    fn send<T: Send + 'static>(&self, endpoint: Endpoint<T>) -> Self::Output<'_, T> {
+        Box::pin(async move {
+            let access_token = self.valid_token().await?;
+            endpoint.check_scopes(&access_token.scope)?;
+
+            self.api
+                .execute_with(endpoint, Some(&access_token), |stale| self.renew(stale))
+                .await
+        })
+    }
+
+    fn base_url(&self) -> &str {
+        self.api.base_url()
+    }
+}
+
+/**
+ * Non-blocking version of [`crate::Registry`].
+ */
+#[derive(Clone, Debug)]
+pub struct Registry {
+    api: Api,
+    merchants: std::sync::Arc<crate::registry::Merchants<SumUp>>,
+}
+
+impl Registry {
+    pub fn new(api: Api, config: crate::Config) -> Self {
+        Self {
+            api,
+            merchants: std::sync::Arc::new(crate::registry::Merchants::new(config)),
+        }
+    }
+
+    pub fn with_credentials(
+        client_id: &str,
+        client_secret: &str,
+        scopes: &[crate::config::Scope],
+        http: crate::config::Http,
+    ) -> crate::Result<Self> {
+        let config = crate::registry::credentials(client_id, client_secret, scopes, http)?;
+
+        Ok(Self::new(Api::from_config(&config)?, config))
+    }
+
+    pub fn insert(&self, merchant_code: &str, refresh_token: impl Into<crate::Secret>) {
+        self.merchants.insert(merchant_code, refresh_token.into());
+    }
+
+    pub fn insert_config(&self, merchant_code: &str, config: crate::Config) {
+        self.merchants.insert_config(merchant_code, config);
+    }
+
+    pub fn remove(&self, merchant_code: &str) -> bool {
+        self.merchants.remove(merchant_code)
+    }
+
+    pub fn merchant_codes(&self) -> Vec<String> {
+        self.merchants.codes()
+    }
+
+    pub async fn merchant(&self, merchant_code: &str) -> crate::Result<SumUp> {
+        match self.merchants.get(merchant_code)? {
+            Ok(sumup) => Ok(sumup),
+            Err(config) => {
+                let sumup = SumUp::with_api(self.api.clone(), config).await?;
+
+                Ok(self.merchants.ready(merchant_code, sumup))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    /**
+     * Non-blocking version of [`crate::test::memory_config`].
+     */
+    async fn memory_config(
+        config: crate::Config,
+        api: impl FnOnce(super::Api) -> super::Api,
+    ) -> crate::Result<(std::sync::Arc<crate::transport::Memory>, super::SumUp)> {
+        let transport = crate::test::transport(&config);
+        let api = api(super::Api::default().with_transport(transport.clone()));
+        let sumup = super::SumUp::with_api(api, config).await?;
+
+        Ok((transport, sumup))
+    }
+
+    #[tokio::test]
+    async fn from() -> crate::Result {
+        let (environment, requests) = crate::test::server(&[crate::test::TOKEN, "[]"]);
+
+        let config = crate::Config {
+            grant_type: crate::config::GrantType::ClientCredentials,
+            environment,
+
+            ..Default::default()
+        };
+
+        let api = super::SumUp::from(config).await?;
+        assert_eq!(api.access_token().access_token.expose(), "access");
+        assert!(requests.recv().unwrap().starts_with("POST /token "));
+
+        let checkouts = api.checkout().list("1").await?;
+        assert!(checkouts.is_empty());
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /v0.1/checkouts?checkout_reference=1 "));
+        assert!(request.contains("authorization: Bearer access"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn memory() -> crate::Result {
+        let config = crate::Config {
+            grant_type: crate::config::GrantType::ClientCredentials,
+
+            ..Default::default()
+        };
+        let (transport, sumup) = memory_config(config, |api| api).await?;
+        transport.push(crate::transport::Response::new(200, "[]"));
+
+        let clone = sumup.clone();
+        let task = tokio::spawn(async move { clone.subaccounts().list().await });
+        assert!(task.await.unwrap()?.is_empty());
+        assert_eq!(
+            transport.requests()[1].url,
+            "https://api.sumup.com/v0.1/me/accounts"
+        );
+
+        transport.push(crate::transport::Response::new(429, "").with_header("Retry-After", "0"));
+        transport.push(crate::transport::Response::new(200, "[]"));
+        assert!(sumup.subaccounts().list().await?.is_empty());
+        assert_eq!(transport.requests().len(), 4);
+
+        transport.push(crate::transport::Response::new(
+            401,
+            "{\"message\": \"Expired\"}",
+        ));
+        transport.push(crate::transport::Response::new(200, crate::test::TOKEN));
+        transport.push(crate::transport::Response::new(200, "[]"));
+        assert!(sumup.subaccounts().list().await?.is_empty());
+        assert_eq!(transport.requests()[5].url, "https://api.sumup.com/token");
+        assert_eq!(transport.requests().len(), 7);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn logout() -> crate::Result {
+        use crate::TokenStore as _;
+
+        let store = crate::token_store::Memory::new();
+        let config = crate::Config {
+            grant_type: crate::config::GrantType::ClientCredentials,
+            token_store: Some(std::sync::Arc::new(store.clone())),
+
+            ..Default::default()
+        };
+        let (transport, sumup) = memory_config(config, |api| api).await?;
+        transport.push(crate::transport::Response::new(200, ""));
+        transport.push(crate::transport::Response::new(
+            400,
+            "{\"message\": \"Invalid\"}",
+        ));
+
+        assert!(sumup.logout().await.is_err());
+        assert_eq!(transport.requests().len(), 3);
+        assert!(sumup.access_token().access_token.is_empty());
+        assert_eq!(store.load()?, None);
+        assert!(matches!(
+            sumup.subaccounts().list().await,
+            Err(crate::Error::Auth("Logged out"))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_config() {
+        for http in [
+            crate::config::Http {
+                root_certificates: vec!["certificate".to_string()],
+
+                ..Default::default()
+            },
+            crate::config::Http {
+                proxy: Some("http://[proxy".to_string()),
+
+                ..Default::default()
+            },
+        ] {
+            assert!(matches!(
+                super::Reqwest::with_config(&http),
+                Err(crate::Error::InvalidConfig(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn registry() -> crate::Result {
+        let config = crate::Config::default();
+        let transport = crate::test::transport(&config);
+        transport.push(crate::transport::Response::new(200, "[]"));
+
+        let api = super::Api::default().with_transport(transport.clone());
+        let registry = super::Registry::new(api, config);
+        registry.insert("M1", "refresh 1");
+
+        let sumup = registry.merchant("M1").await?;
+        assert!(sumup.subaccounts().list().await?.is_empty());
+        assert!(transport.requests()[0]
+            .body
+            .as_ref()
+            .unwrap()
+            .contains(r#""refresh_token":"refresh 1""#));
+        assert!(registry.merchant("M2").await.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,542 @@
+use crate::transport::Method;
+
+/**
+ * A way to send [`Endpoint`] requests to the SumUp API.
+ *
+ * This is implemented by the blocking [`crate::SumUp`] and, with the `async` feature, by
+ * `crate::r#async::SumUp`. Services are generic over it, `Output` being either a
+ * [`crate::Result`] or a future resolving to it.
+ */
+pub trait Client {
+    type Output<'a, T: 'a>
+    where
+        Self: 'a;
+
+    fn send<T: Send + 'static>(&self, endpoint: Endpoint<T>) -> Self::Output<'_, T>;
+
+    /**
+     * Returns the URL the paths of the endpoints are appended to, without a trailing slash.
+     */
+    fn base_url(&self) -> &str;
+
+    /**
+     * Returns a client sending its next `POST` or `PUT` request with `key`. As a key identifies
+     * a single operation, the following ones get their own key as usual.
+     */
+    fn idempotency_key(&self, key: IdempotencyKey) -> Idempotent<'_, Self>
+    where
+        Self: Sized,
+    {
+        Idempotent {
+            client: self,
+            key: std::sync::Mutex::new(Some(key)),
+        }
+    }
+
+    /**
+     * Returns a client sending requests with `policy`, even those which aren't safe to replay.
+     */
+    fn retry_policy(&self, policy: crate::RetryPolicy) -> Retrying<'_, Self>
+    where
+        Self: Sized,
+    {
+        Retrying {
+            client: self,
+            policy,
+        }
+    }
+
+    /**
+     * Returns a client answering a [`crate::response::WithMetadata`], with the status and headers
+     * of the HTTP response, instead of only its decoded body.
+     */
+    fn with_response(&self) -> WithResponse<'_, Self>
+    where
+        Self: Sized,
+    {
+        WithResponse { client: self }
+    }
+
+    fn account(&self) -> crate::services::Account<'_, Self>
+    where
+        Self: Sized,
+    {
+        crate::services::Account::new(self)
+    }
+
+    fn checkout(&self) -> crate::services::Checkout<'_, Self>
+    where
+        Self: Sized,
+    {
+        crate::services::Checkout::new(self)
+    }
+
+    fn customer(&self) -> crate::services::Customer<'_, Self>
+    where
+        Self: Sized,
+    {
+        crate::services::Customer::new(self)
+    }
+
+    fn merchant(&self) -> crate::services::Merchant<'_, Self>
+    where
+        Self: Sized,
+    {
+        crate::services::Merchant::new(self)
+    }
+
+    fn payouts(&self) -> crate::services::Payouts<'_, Self>
+    where
+        Self: Sized,
+    {
+        crate::services::Payouts::new(self)
+    }
+
+    fn personal(&self) -> crate::services::Personal<'_, Self>
+    where
+        Self: Sized,
+    {
+        crate::services::Personal::new(self)
+    }
+
+    fn subaccounts(&self) -> crate::services::Subaccounts<'_, Self>
+    where
+        Self: Sized,
+    {
+        crate::services::Subaccounts::new(self)
+    }
+
+    fn transactions(&self) -> crate::services::Transactions<'_, Self>
+    where
+        Self: Sized,
+    {
+        crate::services::Transactions::new(self)
+    }
+}
+
+/**
+ * A client overriding the idempotency key of the requests it sends, see
+ * [`Client::idempotency_key`].
+ */
+#[derive(Debug)]
+pub struct Idempotent<'c, C> {
+    client: &'c C,
+    /** Taken by the first request needing one */
+    key: std::sync::Mutex<Option<IdempotencyKey>>,
+}
+
+impl<C: Client> Client for Idempotent<'_, C> {
+    type Output<'a, T: 'a>
+        = C::Output<'a, T>
+    where
+        Self: 'a;
+
+    fn send<T: Send + 'static>(&self, mut endpoint: Endpoint<T>) -> Self::Output<'_, T> {
+        if endpoint.mutating() {
+            if let Some(key) = crate::lock(&self.key).take() {
+                endpoint.idempotency_key = Some(key);
+            }
+        }
+
+        self.client.send(endpoint)
+    }
+
+    fn base_url(&self) -> &str {
+        self.client.base_url()
+    }
+}
+
+/**
+ * A client overriding the retry policy of the requests it sends, see [`Client::retry_policy`].
+ */
+#[derive(Clone, Debug)]
+pub struct Retrying<'c, C> {
+    client: &'c C,
+    policy: crate::RetryPolicy,
+}
+
+impl<C: Client> Client for Retrying<'_, C> {
+    type Output<'a, T: 'a>
+        = C::Output<'a, T>
+    where
+        Self: 'a;
+
+    fn send<T: Send + 'static>(&self, mut endpoint: Endpoint<T>) -> Self::Output<'_, T> {
+        endpoint.retry_policy = Some(self.policy.clone());
+
+        self.client.send(endpoint)
+    }
+
+    fn base_url(&self) -> &str {
+        self.client.base_url()
+    }
+}
+
+/**
+ * A client answering the metadata of the responses it receives, see [`Client::with_response`].
+ */
+#[derive(Clone, Debug)]
+pub struct WithResponse<'c, C> {
+    client: &'c C,
+}
+
+impl<C: Client> Client for WithResponse<'_, C> {
+    type Output<'a, T: 'a>
+        = C::Output<'a, crate::response::WithMetadata<T>>
+    where
+        Self: 'a;
+
+    fn send<T: Send + 'static>(&self, endpoint: Endpoint<T>) -> Self::Output<'_, T> {
+        self.client.send(endpoint.with_response())
+    }
+
+    fn base_url(&self) -> &str {
+        self.client.base_url()
+    }
+}
+
+/**
+ * The `Idempotency-Key` sent with `POST` and `PUT` requests, so SumUp processes a replayed
+ * request only once.
+ *
+ * A key is generated for each call, use [`Client::idempotency_key`] to supply your own, for
+ * example to replay a call which timed out.
+ */
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    /**
+     * Generates a random (version 4) UUID, from the cryptographically secure random generator of
+     * the system so keys of distinct processes don't collide.
+     */
+    pub fn new() -> crate::Result<Self> {
+        let bits = u128::from_be_bytes(crate::random()?);
+        let uuid = bits & !(0xf << 76) & !(0x3 << 62) | (0x4 << 76) | (0x2 << 62);
+
+        Ok(Self(format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            uuid >> 96,
+            (uuid >> 80) & 0xffff,
+            (uuid >> 64) & 0xffff,
+            (uuid >> 48) & 0xffff,
+            uuid & 0xffff_ffff_ffff,
+        )))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for IdempotencyKey {
+    fn from(key: &str) -> Self {
+        Self(key.to_string())
+    }
+}
+
+impl From<String> for IdempotencyKey {
+    fn from(key: String) -> Self {
+        Self(key)
+    }
+}
+
+type Decoder<T> = Box<
+    dyn FnOnce(&crate::transport::Request, crate::transport::Response) -> crate::Result<T> + Send,
+>;
+
+/**
+ * A request to a SumUp API endpoint, answering a `T`.
+ */
+pub struct Endpoint<T = ()> {
+    pub(crate) method: Method,
+    pub(crate) path: String,
+    pub(crate) payload: serde_json::Result<Option<serde_json::Value>>,
+    /** Whether the request can be replayed without side effects */
+    pub(crate) idempotent: bool,
+    /** The key to send instead of a generated one, for `POST` and `PUT` requests */
+    pub(crate) idempotency_key: Option<IdempotencyKey>,
+    pub(crate) retry_policy: Option<crate::RetryPolicy>,
+    pub(crate) group: Option<crate::rate_limit::Group>,
+    /** The scopes the access token must grant */
+    pub(crate) scopes: Vec<crate::config::Scope>,
+    decoder: Decoder<T>,
+}
+
+impl<T> std::fmt::Debug for Endpoint<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Endpoint")
+            .field("method", &self.method)
+            .field("path", &self.path)
+            .field("payload", &self.payload)
+            .field("idempotent", &self.idempotent)
+            .field("idempotency_key", &self.idempotency_key)
+            .field("retry_policy", &self.retry_policy)
+            .field("group", &self.group)
+            .field("scopes", &self.scopes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: serde::de::DeserializeOwned> Endpoint<T> {
+    pub(crate) fn new(method: Method, path: &str) -> Self {
+        Self {
+            method,
+            path: path.to_string(),
+            payload: Ok(None),
+            idempotent: !matches!(method, Method::Post),
+            idempotency_key: None,
+            retry_policy: None,
+            group: None,
+            scopes: Vec::new(),
+            decoder: Box::new(|_, response| response.json()),
+        }
+    }
+
+    pub(crate) fn get(path: &str) -> Self {
+        Self::new(Method::Get, path)
+    }
+
+    pub(crate) fn post(path: &str, payload: impl serde::Serialize) -> Self {
+        Self::new(Method::Post, path).with_payload(payload)
+    }
+
+    pub(crate) fn put(path: &str, payload: impl serde::Serialize) -> Self {
+        Self::new(Method::Put, path).with_payload(payload)
+    }
+
+    pub(crate) fn delete(path: &str) -> Self {
+        Self::new(Method::Delete, path)
+    }
+}
+
+impl<T> Endpoint<T> {
+    pub(crate) fn with_payload(mut self, payload: impl serde::Serialize) -> Self {
+        self.payload = serde_json::to_value(payload).map(Some);
+        self
+    }
+
+    pub(crate) fn with_group(mut self, group: crate::rate_limit::Group) -> Self {
+        self.group = Some(group);
+        self
+    }
+
+    pub(crate) fn with_scopes(mut self, scopes: &[crate::config::Scope]) -> Self {
+        self.scopes = scopes.to_vec();
+        self
+    }
+
+    /**
+     * Whether the request is sent with an idempotency key.
+     */
+    pub(crate) fn mutating(&self) -> bool {
+        matches!(self.method, Method::Post | Method::Put)
+    }
+
+    pub(crate) fn with_idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent = idempotent;
+        self
+    }
+
+    /**
+     * Answers the idempotency key sent instead of the response body.
+     */
+    pub(crate) fn with_idempotency_key_output(self) -> Endpoint<IdempotencyKey> {
+        self.with_decoder(|request, response| {
+            response.check()?;
+
+            let idempotency_key = request.header("Idempotency-Key").unwrap_or_default();
+
+            Ok(idempotency_key.into())
+        })
+    }
+
+    pub(crate) fn with_decoder<U>(
+        self,
+        decoder: impl FnOnce(&crate::transport::Request, crate::transport::Response) -> crate::Result<U>
+            + Send
+            + 'static,
+    ) -> Endpoint<U> {
+        self.map_decoder(|_| Box::new(decoder))
+    }
+
+    /**
+     * Answers the decoded body along with the status and headers of the response.
+     */
+    pub(crate) fn with_response(self) -> Endpoint<crate::response::WithMetadata<T>>
+    where
+        T: 'static,
+    {
+        self.map_decoder(|decoder| {
+            Box::new(move |request, response| {
+                let status = response.status;
+                let headers = response.headers.clone();
+                let body = response.body.clone();
+                let value = decoder(request, response)?;
+
+                Ok(crate::response::WithMetadata {
+                    status,
+                    headers,
+                    body,
+                    value,
+                })
+            })
+        })
+    }
+
+    fn map_decoder<U>(self, f: impl FnOnce(Decoder<T>) -> Decoder<U>) -> Endpoint<U> {
+        Endpoint {
+            method: self.method,
+            path: self.path,
+            payload: self.payload,
+            idempotent: self.idempotent,
+            idempotency_key: self.idempotency_key,
+            retry_policy: self.retry_policy,
+            group: self.group,
+            scopes: self.scopes,
+            decoder: f(self.decoder),
+        }
+    }
+
+    /**
+     * Returns the policy overridden for this request, otherwise `default` if the request is
+     * idempotent.
+     */
+    pub(crate) fn retry_policy(&self, default: &crate::RetryPolicy) -> crate::RetryPolicy {
+        match &self.retry_policy {
+            Some(policy) => policy.clone(),
+            None if self.idempotent => default.clone(),
+            None => crate::RetryPolicy::none(),
+        }
+    }
+
+    /**
+     * Fails with the scopes required but not in `granted`. An empty `granted` is unknown, and
+     * so isn't checked.
+     */
+    pub(crate) fn check_scopes(&self, granted: &crate::config::Scopes) -> crate::Result {
+        if granted.is_empty() {
+            return Ok(());
+        }
+
+        let missing = self
+            .scopes
+            .iter()
+            .filter(|required| !granted.iter().any(|x| x.includes(**required)))
+            .copied()
+            .collect::<Vec<_>>();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(crate::Error::MissingScope(missing.into()))
+        }
+    }
+
+    /**
+     * Builds the HTTP request, and the function decoding its response.
+     */
+    pub(crate) fn into_request(
+        self,
+        base_url: &str,
+        access_token: Option<&crate::AccessToken>,
+    ) -> crate::Result<(crate::transport::Request, Decoder<T>)> {
+        let url = format!("{base_url}{}", self.path);
+        let mut request = crate::transport::Request {
+            method: self.method,
+            url,
+            headers: Vec::new(),
+            body: None,
+        };
+
+        if let Some(access_token) = access_token {
+            request
+                .headers
+                .push(("Authorization".to_string(), access_token.bearer()));
+        }
+
+        if self.mutating() {
+            let idempotency_key = match self.idempotency_key {
+                Some(idempotency_key) => idempotency_key,
+                None => IdempotencyKey::new()?,
+            };
+            request
+                .headers
+                .push(("Idempotency-Key".to_string(), idempotency_key.to_string()));
+        }
+
+        if let Some(payload) = self.payload? {
+            request
+                .headers
+                .push(("Content-Type".to_string(), "application/json".to_string()));
+            request.body = Some(payload.to_string());
+        }
+
+        Ok((request, self.decoder))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn idempotency_key() -> crate::Result {
+        let key = crate::IdempotencyKey::new()?;
+        let bytes = key.as_str().as_bytes();
+
+        assert_eq!(key.as_str().len(), 36);
+        assert_eq!(bytes[14], b'4');
+        assert!(matches!(bytes[19], b'8' | b'9' | b'a' | b'b'));
+        assert_ne!(key, crate::IdempotencyKey::new()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn idempotent() -> crate::Result {
+        use crate::Client as _;
+
+        let (transport, sumup) =
+            crate::test::memory_with(|api| api.with_retry_policy(crate::RetryPolicy::none()))?;
+
+        transport.push(crate::transport::Response::new(204, ""));
+        let key = sumup.transactions().refund(1, 2.)?;
+        let requests = transport.requests();
+        assert_eq!(requests[1].header("Idempotency-Key"), Some(key.as_str()));
+        assert_eq!(requests[1].method, crate::transport::Method::Post);
+
+        transport.push(crate::transport::Response::new(200, "[]"));
+        sumup.checkout().list("1")?;
+        assert_eq!(transport.requests()[2].header("Idempotency-Key"), None);
+
+        transport.push(crate::transport::Response::new(502, ""));
+        transport.push(crate::transport::Response::new(200, "{}"));
+        let idempotent = sumup.idempotency_key("pay-1".into());
+        let key = idempotent
+            .retry_policy(crate::RetryPolicy {
+                initial_backoff: std::time::Duration::ZERO,
+
+                ..Default::default()
+            })
+            .checkout()
+            .pay("1", "1", "1", None)?;
+        assert_eq!(key.as_str(), "pay-1");
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 5);
+        assert_eq!(requests[3].header("Idempotency-Key"), Some("pay-1"));
+        assert_eq!(requests[4].header("Idempotency-Key"), Some("pay-1"));
+
+        transport.push(crate::transport::Response::new(200, "{}"));
+        let key = idempotent.checkout().pay("2", "1", "1", None)?;
+        assert_ne!(key.as_str(), "pay-1");
+        assert_eq!(transport.requests().len(), 6);
+
+        Ok(())
+    }
+}
@@ -1,17 +1,60 @@
-#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
 #[cfg_attr(test, serde(deny_unknown_fields))]
 pub struct AccessToken {
-    pub access_token: String,
+    pub access_token: crate::Secret,
     pub token_type: String,
     /** The number of seconds the access token will be valid. */
     pub expires_in: Option<u32>,
     pub scope: crate::config::Scopes,
-    pub refresh_token: Option<String>,
+    pub refresh_token: Option<crate::Secret>,
+    /** When the access token was issued, which defaults to when it was received */
+    #[serde(default = "std::time::SystemTime::now")]
+    pub issued_at: std::time::SystemTime,
+}
+
+impl Default for AccessToken {
+    fn default() -> Self {
+        Self {
+            access_token: crate::Secret::default(),
+            token_type: String::new(),
+            expires_in: None,
+            scope: Default::default(),
+            refresh_token: None,
+            issued_at: std::time::SystemTime::now(),
+        }
+    }
 }
 
 impl AccessToken {
     pub fn bearer(&self) -> String {
-        format!("Bearer {}", self.access_token)
+        format!("Bearer {}", self.access_token.expose())
+    }
+
+    /**
+     * When the access token stops being valid, if known.
+     */
+    pub fn expires_at(&self) -> Option<std::time::SystemTime> {
+        self.expires_in
+            .map(|x| self.issued_at + std::time::Duration::from_secs(x.into()))
+    }
+
+    /**
+     * Keeps the refresh token of `previous` when a refreshed token comes without one.
+     */
+    pub(crate) fn or_refresh_token_of(mut self, previous: &AccessToken) -> Self {
+        if self.refresh_token.is_none() {
+            self.refresh_token.clone_from(&previous.refresh_token);
+        }
+
+        self
+    }
+
+    /**
+     * Whether the access token is expired, or will be in `margin`.
+     */
+    pub fn expires_within(&self, margin: std::time::Duration) -> bool {
+        self.expires_at()
+            .is_some_and(|x| x <= std::time::SystemTime::now() + margin)
     }
 }
 
@@ -322,16 +365,17 @@ pub enum Status {
     ChargeBack,
 }
 
-impl ToString for Status {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
             Status::Successful => "SUCCESSFUL",
             Status::Cancelled => "CANCELLED",
             Status::Failed => "FAILED",
             Status::Refunded => "REFUNDED",
             Status::ChargeBack => "CHARGE_BACK",
-        }
-        .to_string()
+        };
+
+        f.write_str(s)
     }
 }
 
@@ -347,9 +391,9 @@ pub enum PaymentType {
     Unknown,
 }
 
-impl ToString for PaymentType {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for PaymentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
             PaymentType::Cash => "CASH",
             PaymentType::Pos => "POS",
             PaymentType::Ecom => "ECOM",
@@ -357,8 +401,9 @@ impl ToString for PaymentType {
             PaymentType::Moto => "MOTO",
             PaymentType::Boleto => "BOLETO",
             PaymentType::Unknown => "UNKNOWN",
-        }
-        .to_string()
+        };
+
+        f.write_str(s)
     }
 }
 
@@ -369,14 +414,15 @@ pub enum Type {
     ChargeBack,
 }
 
-impl ToString for Type {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
             Type::Payment => "PAYMENT",
             Type::Refund => "REFUND",
             Type::ChargeBack => "CHARGE_BACK",
-        }
-        .to_string()
+        };
+
+        f.write_str(s)
     }
 }
 
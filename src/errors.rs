@@ -6,22 +6,129 @@ pub enum Error {
     Api(Response),
     #[error("{0}")]
     Auth(&'static str),
+    /** A 4xx or 5xx response whose body isn't a SumUp API error */
+    #[error("HTTP {status}: {body}")]
+    Http { status: u16, body: String },
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
     #[error("Invalid scope: {0}")]
     InvalidScope(String),
+    /** An authorization callback whose state doesn't match the request, possibly forged */
+    #[error("Invalid authorization state")]
+    InvalidState,
     #[error("{0}")]
     Io(#[from] std::io::Error),
+    /** The access token doesn't grant the scopes the endpoint requires */
+    #[error("Missing scopes: {0}")]
+    MissingScope(crate::config::Scopes),
     #[error("{0}")]
     Json(#[from] serde_json::Error),
+    /** The user denied the authorization, or SumUp rejected the authorization request */
+    #[error("OAuth error {error}: {}", description.as_deref().unwrap_or_default())]
+    OAuth {
+        error: String,
+        description: Option<String>,
+    },
+    /** The request couldn't be sent or its response received, like on DNS or TLS failures */
+    #[error("Transport error: {0}")]
+    Transport(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /** No merchant is registered with this code */
+    #[error("Unknown merchant: {0}")]
+    UnknownMerchant(String),
+}
+
+impl Error {
+    /**
+     * The error for a 4xx or 5xx response with `body`.
+     */
+    pub(crate) fn status(status: u16, body: &str) -> Self {
+        match serde_json::from_str::<Response>(body) {
+            Ok(mut response) => {
+                for message in response.messages_mut() {
+                    message.status.get_or_insert(status);
+                }
+
+                Self::Api(response)
+            }
+            Err(_) => Self::Http {
+                status,
+                body: body.to_string(),
+            },
+        }
+    }
+
+    /**
+     * The messages of an API error, empty for other errors.
+     */
+    pub fn messages(&self) -> &[Message] {
+        match self {
+            Self::Api(response) => response.messages(),
+            _ => &[],
+        }
+    }
+
+    /**
+     * The HTTP status of an API error.
+     */
+    pub fn http_status(&self) -> Option<u16> {
+        match self {
+            Self::Http { status, .. } => Some(*status),
+            _ => self.messages().iter().find_map(|x| x.status),
+        }
+    }
+
+    /**
+     * The code of the first message of an API error.
+     */
+    pub fn error_code(&self) -> Option<&ErrorCode> {
+        self.messages().iter().find_map(|x| x.error_code.as_ref())
+    }
+
+    /**
+     * The request parameter a validation error is about.
+     */
+    pub fn param(&self) -> Option<&str> {
+        self.messages().iter().find_map(|x| x.param.as_deref())
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.http_status() == Some(404) || self.has_code(|x| *x == ErrorCode::NotFound)
+    }
+
+    /**
+     * Whether the request was rejected because of invalid or missing parameters.
+     */
+    pub fn is_validation(&self) -> bool {
+        self.has_code(|x| matches!(x, ErrorCode::Invalid | ErrorCode::Missing))
+    }
+
+    /**
+     * Whether sending the request again may succeed, see [`crate::RetryPolicy`].
+     */
+    pub fn is_retryable(&self) -> bool {
+        match self.http_status() {
+            Some(status) => crate::retry::is_retryable_status(status),
+            None => crate::retry::is_transient(self),
+        }
+    }
+
+    fn has_code(&self, predicate: impl Fn(&ErrorCode) -> bool) -> bool {
+        self.messages()
+            .iter()
+            .filter_map(|x| x.error_code.as_ref())
+            .any(predicate)
+    }
 }
 
 impl From<ureq::Error> for Error {
     fn from(error: ureq::Error) -> Self {
-        error
-            .into_response()
-            .unwrap()
-            .into_json()
-            .map(Self::Api)
-            .unwrap_or_else(Self::from)
+        match error {
+            ureq::Error::Status(status, response) => match response.into_string() {
+                Ok(body) => Self::status(status, &body),
+                Err(error) => Self::Transport(Box::new(error)),
+            },
+            ureq::Error::Transport(error) => Self::Transport(Box::new(error)),
+        }
     }
 }
 
@@ -32,17 +139,131 @@ pub enum Response {
     Messages(Vec<Message>),
 }
 
+impl Response {
+    pub fn messages(&self) -> &[Message] {
+        match self {
+            Self::Message(message) => std::slice::from_ref(message),
+            Self::Messages(messages) => messages,
+        }
+    }
+
+    fn messages_mut(&mut self) -> &mut [Message] {
+        match self {
+            Self::Message(message) => std::slice::from_mut(message),
+            Self::Messages(messages) => messages,
+        }
+    }
+}
+
 #[derive(Debug, serde::Deserialize)]
 #[cfg_attr(test, serde(deny_unknown_fields))]
 pub struct Message {
     #[serde(alias = "error_message")]
     pub message: String,
     pub instance: Option<String>,
-    pub error_code: Option<String>,
+    pub error_code: Option<ErrorCode>,
     pub param: Option<String>,
     #[serde(rename = "type")]
     pub ty: Option<String>,
     pub title: Option<String>,
-    pub status: Option<u8>,
+    pub status: Option<u16>,
     pub detail: Option<String>,
 }
+
+/**
+ * The documented values of [`Message::error_code`].
+ */
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize)]
+#[serde(from = "String")]
+pub enum ErrorCode {
+    CheckoutProcessed,
+    DuplicatedCheckout,
+    Forbidden,
+    Invalid,
+    InvalidAccessToken,
+    InvalidPassword,
+    Missing,
+    NotAuthorized,
+    NotFound,
+    Other(String),
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::CheckoutProcessed => "CHECKOUT_PROCESSED",
+            Self::DuplicatedCheckout => "DUPLICATED_CHECKOUT",
+            Self::Forbidden => "FORBIDDEN",
+            Self::Invalid => "INVALID",
+            Self::InvalidAccessToken => "INVALID_ACCESS_TOKEN",
+            Self::InvalidPassword => "INVALID_PASSWORD",
+            Self::Missing => "MISSING",
+            Self::NotAuthorized => "NOT_AUTHORIZED",
+            Self::NotFound => "NOT_FOUND",
+            Self::Other(code) => code,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<String> for ErrorCode {
+    fn from(code: String) -> Self {
+        match code.as_str() {
+            "CHECKOUT_PROCESSED" => Self::CheckoutProcessed,
+            "DUPLICATED_CHECKOUT" => Self::DuplicatedCheckout,
+            "FORBIDDEN" => Self::Forbidden,
+            "INVALID" => Self::Invalid,
+            "INVALID_ACCESS_TOKEN" => Self::InvalidAccessToken,
+            "INVALID_PASSWORD" => Self::InvalidPassword,
+            "MISSING" => Self::Missing,
+            "NOT_AUTHORIZED" => Self::NotAuthorized,
+            "NOT_FOUND" => Self::NotFound,
+            _ => Self::Other(code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn predicates() {
+        let error = crate::Error::status(
+            404,
+            r#"{"message": "Not found", "error_code": "NOT_FOUND"}"#,
+        );
+        assert_eq!(error.error_code(), Some(&crate::ErrorCode::NotFound));
+        assert_eq!(error.http_status(), Some(404));
+        assert!(error.is_not_found());
+        assert!(!error.is_validation());
+        assert!(!error.is_retryable());
+
+        let error = crate::Error::status(
+            400,
+            r#"[{"message": "Invalid", "error_code": "INVALID", "param": "amount"}, {"message": "Missing", "error_code": "MISSING", "param": "currency"}]"#,
+        );
+        assert!(error.is_validation());
+        assert_eq!(error.param(), Some("amount"));
+        assert_eq!(error.messages().len(), 2);
+
+        let error = crate::Error::status(
+            409,
+            r#"{"message": "Other", "error_code": "SOMETHING_NEW"}"#,
+        );
+        assert_eq!(
+            error.error_code(),
+            Some(&crate::ErrorCode::Other("SOMETHING_NEW".to_string()))
+        );
+
+        assert!(crate::Error::status(503, "Unavailable").is_retryable());
+        assert!(crate::Error::status(404, "").is_not_found());
+        assert!(
+            crate::Error::from(std::io::Error::from(std::io::ErrorKind::TimedOut)).is_retryable()
+        );
+        assert!(!crate::Error::Auth("Denied").is_retryable());
+    }
+}
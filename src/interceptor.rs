@@ -0,0 +1,146 @@
+use crate::transport::{Request, Response};
+
+/**
+ * A hook called around every HTTP request sent by [`crate::Api`], retries included.
+ *
+ * Interceptors see requests in the order they were registered, and responses in reverse order.
+ */
+pub trait Interceptor: std::fmt::Debug + Send + Sync {
+    /**
+     * Called before `request` is sent. Returning an error aborts the call.
+     */
+    fn request(&self, request: &mut Request) -> crate::Result {
+        let _ = request;
+
+        Ok(())
+    }
+
+    /**
+     * Called with the response to `request`, or the error preventing to get one, received
+     * `elapsed` after sending it.
+     */
+    fn response(
+        &self,
+        request: &Request,
+        result: &mut crate::Result<Response>,
+        elapsed: std::time::Duration,
+    ) {
+        let _ = (request, result, elapsed);
+    }
+}
+
+impl<T: Interceptor + ?Sized> Interceptor for std::sync::Arc<T> {
+    fn request(&self, request: &mut Request) -> crate::Result {
+        (**self).request(request)
+    }
+
+    fn response(
+        &self,
+        request: &Request,
+        result: &mut crate::Result<Response>,
+        elapsed: std::time::Duration,
+    ) {
+        (**self).response(request, result, elapsed)
+    }
+}
+
+pub(crate) type Interceptors = Vec<std::sync::Arc<dyn Interceptor>>;
+
+pub(crate) fn request(interceptors: &Interceptors, mut request: Request) -> crate::Result<Request> {
+    for interceptor in interceptors {
+        interceptor.request(&mut request)?;
+    }
+
+    Ok(request)
+}
+
+pub(crate) fn response(
+    interceptors: &Interceptors,
+    request: &Request,
+    mut result: crate::Result<Response>,
+    elapsed: std::time::Duration,
+) -> crate::Result<Response> {
+    for interceptor in interceptors.iter().rev() {
+        interceptor.response(request, &mut result, elapsed);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    #[derive(Debug, Default)]
+    struct Audit {
+        calls: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl crate::Interceptor for Audit {
+        fn request(&self, request: &mut crate::transport::Request) -> crate::Result {
+            request
+                .headers
+                .push(("X-Correlation-Id".to_string(), "42".to_string()));
+
+            Ok(())
+        }
+
+        fn response(
+            &self,
+            request: &crate::transport::Request,
+            result: &mut crate::Result<crate::transport::Response>,
+            _: std::time::Duration,
+        ) {
+            let status = match result {
+                Ok(response) => response.status.to_string(),
+                Err(_) => "error".to_string(),
+            };
+
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{} {status}", request.url));
+
+            if let Ok(response) = result {
+                if response.status == 404 {
+                    *response = crate::transport::Response::new(200, "[]");
+                }
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct Deny;
+
+    impl crate::Interceptor for Deny {
+        fn request(&self, _: &mut crate::transport::Request) -> crate::Result {
+            Err(crate::Error::Auth("Denied"))
+        }
+    }
+
+    #[test]
+    fn interceptor() -> crate::Result {
+        let audit = std::sync::Arc::new(Audit::default());
+        let (transport, sumup) =
+            crate::test::memory_with(|api| api.with_interceptor(audit.clone()))?;
+        transport.push(crate::transport::Response::new(404, ""));
+
+        assert!(sumup.subaccounts().list()?.is_empty());
+        assert_eq!(
+            transport.requests()[1].header("X-Correlation-Id"),
+            Some("42")
+        );
+        assert_eq!(
+            *audit.calls.lock().unwrap(),
+            [
+                "https://api.sumup.com/token 200",
+                "https://api.sumup.com/v0.1/me/accounts 404"
+            ]
+        );
+
+        assert!(matches!(
+            crate::test::memory_with(|api| api.with_interceptor(Deny)),
+            Err(crate::Error::Auth("Denied"))
+        ));
+
+        Ok(())
+    }
+}
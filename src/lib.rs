@@ -1,20 +1,79 @@
 #[warn(warnings)]
 pub mod config;
 pub mod errors;
+pub mod loopback;
+pub mod rate_limit;
+pub mod registry;
+pub mod response;
 pub mod services;
+pub mod token_store;
+pub mod transport;
+
+#[cfg(feature = "async")]
+pub mod r#async;
 
 mod api;
+mod client;
 mod entity;
+mod interceptor;
+mod redact;
+mod retry;
+mod secret;
+mod session;
+mod telemetry;
 
+pub use api::Api;
+pub use client::{Client, Endpoint, IdempotencyKey, Idempotent, Retrying, WithResponse};
 pub use config::Config;
 pub use entity::*;
 pub use errors::*;
+pub use interceptor::Interceptor;
+pub use rate_limit::RateLimiter;
+pub use redact::Redactor;
+pub use registry::Registry;
+pub use retry::RetryPolicy;
+pub use secret::Secret;
+pub use token_store::TokenStore;
+
+/**
+ * How long before its expiry an access token is renewed.
+ */
+pub(crate) const EXPIRY_MARGIN: std::time::Duration = std::time::Duration::from_secs(60);
+
+/**
+ * Locks `mutex`, even if poisoned by a thread which panicked while holding it.
+ */
+pub(crate) fn lock<T>(mutex: &std::sync::Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/**
+ * Returns `N` bytes from the cryptographically secure random generator of the system.
+ */
+pub(crate) fn random<const N: usize>() -> Result<[u8; N]> {
+    use ring::rand::SecureRandom as _;
 
-use api::Api;
+    let mut bytes = [0; N];
+    ring::rand::SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| Error::Auth("Unable to generate random bytes"))?;
 
+    Ok(bytes)
+}
+
+/**
+ * A client authenticated with an access token, renewed when about to expire or rejected.
+ *
+ * It is `Send + Sync`, and clones share the access token, so a client can be cloned into worker
+ * threads or kept in shared state, each renewal being done once for all of them.
+ */
 #[derive(Clone, Debug)]
 pub struct SumUp {
-    access_token: AccessToken,
+    session: session::Session,
+    /** Held while renewing the access token */
+    renewal: std::sync::Arc<std::sync::Mutex<()>>,
     api: Api,
     config: Config,
 }
@@ -27,12 +86,22 @@ impl SumUp {
     }
 
     pub fn from(config: Config) -> Result<Self> {
-        let api = Api::new();
+        let api = Api::from_config(&config)?;
+
+        Self::with_api(api, config)
+    }
+
+    /**
+     * Creates a client sending its requests through `api`, whose base URL supersedes
+     * `config.environment`.
+     */
+    pub fn with_api(api: Api, config: Config) -> Result<Self> {
         let authorization = services::Authorization::new(&api, &config);
         let access_token = authorization.token()?;
 
         let sumup = Self {
-            access_token,
+            session: session::Session::new(access_token),
+            renewal: Default::default(),
             api,
             config,
         };
@@ -40,80 +109,151 @@ impl SumUp {
         Ok(sumup)
     }
 
-    pub fn access_token(&self) -> &AccessToken {
-        &self.access_token
+    pub fn access_token(&self) -> AccessToken {
+        self.session.access_token()
     }
 
     /**
      * <https://developer.sumup.com/docs/api/generate-a-token/>
      */
-    pub fn refresh_token(&mut self, refresh_token: Option<&str>) -> crate::Result {
-        let refresh_token = refresh_token
-            .or(self.access_token.refresh_token.as_deref())
-            .ok_or(crate::Error::Auth("There is no refresh token"))?;
-        self.access_token = self.authorization().refresh_token(refresh_token)?;
+    pub fn refresh_token(&self, refresh_token: Option<&str>) -> crate::Result {
+        let _renewal = lock(&self.renewal);
+        let access_token = self.access_token();
+        let refresh_token = session::refresh_token(&access_token, refresh_token)?;
+        let token = self.authorization().refresh_token(refresh_token)?;
+        self.session
+            .replace(&self.authorization(), &access_token, token)?;
 
         Ok(())
     }
 
+    /**
+     * Revokes the access and refresh tokens, then forgets them, removing them from the token
+     * store. Requests fail afterwards, except with an API key which can't be revoked.
+     *
+     * The tokens are forgotten even if revoking them fails, the first error being returned.
+     */
+    pub fn logout(&self) -> crate::Result {
+        let _renewal = lock(&self.renewal);
+        let mut revoked = Ok(());
+
+        for token in self.session.revocable(&self.config) {
+            revoked = revoked.and(self.authorization().revoke(token.expose()));
+        }
+
+        self.session
+            .logout(&self.authorization(), &self.config, revoked)
+    }
+
+    /**
+     * Returns the access token, renewed first if it is about to expire.
+     */
+    fn valid_token(&self) -> crate::Result<AccessToken> {
+        let access_token = self.session.current()?;
+
+        if access_token.expires_within(EXPIRY_MARGIN) {
+            if let Some(access_token) = self.renew(access_token.clone())? {
+                return Ok(access_token);
+            }
+        }
+
+        Ok(access_token)
+    }
+
+    /**
+     * Replaces the `stale` access token, unless another call already did.
+     */
+    fn renew(&self, stale: AccessToken) -> crate::Result<Option<AccessToken>> {
+        let _renewal = lock(&self.renewal);
+
+        if let Some(access_token) = self.session.replacement(&stale) {
+            return Ok(Some(access_token));
+        }
+
+        let Some(token) = self.authorization().renew(&stale)?.transpose()? else {
+            return Ok(None);
+        };
+        log::debug!("Renewed the access token");
+
+        self.session
+            .replace(&self.authorization(), &stale, token)
+            .map(Some)
+    }
+
     /**
      * <https://developer.sumup.com/docs/api/account-details/>
      */
-    pub fn account(&self) -> crate::services::Account {
-        services::Account::new(&self.api, &self.access_token)
+    pub fn account(&self) -> crate::services::Account<'_, Self> {
+        services::Account::new(self)
     }
 
     /**
      * <https://developer.sumup.com/docs/api/authorization/>
      */
-    pub fn authorization(&self) -> crate::services::Authorization {
+    pub fn authorization(&self) -> crate::services::Authorization<'_, Api> {
         services::Authorization::new(&self.api, &self.config)
     }
 
     /**
      * <https://developer.sumup.com/docs/api/checkouts/>
      */
-    pub fn checkout(&self) -> crate::services::Checkout {
-        services::Checkout::new(&self.api, &self.access_token)
+    pub fn checkout(&self) -> crate::services::Checkout<'_, Self> {
+        services::Checkout::new(self)
     }
 
     /**
      * <https://developer.sumup.com/docs/api/customers/>
      */
-    pub fn customer(&self) -> crate::services::Customer {
-        services::Customer::new(&self.api, &self.access_token)
+    pub fn customer(&self) -> crate::services::Customer<'_, Self> {
+        services::Customer::new(self)
     }
 
     /**
      * <https://developer.sumup.com/docs/api/merchant-account/>
      */
-    pub fn merchant(&self) -> crate::services::Merchant {
-        services::Merchant::new(&self.api, &self.access_token)
+    pub fn merchant(&self) -> crate::services::Merchant<'_, Self> {
+        services::Merchant::new(self)
     }
 
-    pub fn payouts(&self) -> crate::services::Payouts {
-        services::Payouts::new(&self.api, &self.access_token)
+    pub fn payouts(&self) -> crate::services::Payouts<'_, Self> {
+        services::Payouts::new(self)
     }
 
     /**
      * <https://developer.sumup.com/docs/api/personal-account/>
      */
-    pub fn personal(&self) -> crate::services::Personal {
-        services::Personal::new(&self.api, &self.access_token)
+    pub fn personal(&self) -> crate::services::Personal<'_, Self> {
+        services::Personal::new(self)
     }
 
     /**
      * <https://developer.sumup.com/docs/api/subaccounts/>
      */
-    pub fn subaccounts(&self) -> crate::services::Subaccounts {
-        services::Subaccounts::new(&self.api, &self.access_token)
+    pub fn subaccounts(&self) -> crate::services::Subaccounts<'_, Self> {
+        services::Subaccounts::new(self)
     }
 
     /**
      * <https://developer.sumup.com/docs/api/transactions/>
      */
-    pub fn transactions(&self) -> crate::services::Transactions {
-        services::Transactions::new(&self.api, &self.access_token)
+    pub fn transactions(&self) -> crate::services::Transactions<'_, Self> {
+        services::Transactions::new(self)
+    }
+}
+
+impl Client for SumUp {
+    type Output<'a, T: 'a> = crate::Result<T>;
+
+    fn send<T: Send + 'static>(&self, endpoint: Endpoint<T>) -> Self::Output<'_, T> {
+        let access_token = self.valid_token()?;
+        endpoint.check_scopes(&access_token.scope)?;
+
+        self.api
+            .execute_with(endpoint, Some(&access_token), |stale| self.renew(stale))
+    }
+
+    fn base_url(&self) -> &str {
+        self.api.base_url()
     }
 }
 
@@ -129,9 +269,9 @@ mod test {
 
         let config = crate::Config {
             client_id: std::env::var("CLIENT_ID").unwrap(),
-            client_secret: std::env::var("CLIENT_SECRET").unwrap(),
+            client_secret: std::env::var("CLIENT_SECRET").unwrap().into(),
             username: std::env::var("USERNAME").ok(),
-            password: std::env::var("PASSWORD").ok(),
+            password: std::env::var("PASSWORD").ok().map(Into::into),
             grant_type: crate::config::GrantType::Password,
 
             ..Default::default()
@@ -140,19 +280,403 @@ mod test {
         crate::SumUp::from(config)
     }
 
+    /**
+     * Returns a client authenticated with [`TOKEN`] through an in-memory transport, whose queued
+     * responses answer the following requests, none being retried.
+     */
+    pub(crate) fn memory() -> crate::Result<(std::sync::Arc<crate::transport::Memory>, crate::SumUp)>
+    {
+        memory_with(|api| api.with_retry_policy(crate::RetryPolicy::none()))
+    }
+
+    /**
+     * Like [`memory`], with the API configured by `api`.
+     */
+    pub(crate) fn memory_with(
+        api: impl FnOnce(crate::Api) -> crate::Api,
+    ) -> crate::Result<(std::sync::Arc<crate::transport::Memory>, crate::SumUp)> {
+        let config = crate::Config {
+            grant_type: crate::config::GrantType::ClientCredentials,
+
+            ..Default::default()
+        };
+
+        memory_config(config, api)
+    }
+
+    /**
+     * Like [`memory_with`], authenticated as set by `config`.
+     */
+    pub(crate) fn memory_config(
+        config: crate::Config,
+        api: impl FnOnce(crate::Api) -> crate::Api,
+    ) -> crate::Result<(std::sync::Arc<crate::transport::Memory>, crate::SumUp)> {
+        let transport = transport(&config);
+        let api = api(crate::Api::default().with_transport(transport.clone()));
+        let sumup = crate::SumUp::with_api(api, config)?;
+
+        Ok((transport, sumup))
+    }
+
+    /**
+     * Returns an in-memory transport answering the first request with [`TOKEN`], unless `config`
+     * authenticates with an API key.
+     */
+    pub(crate) fn transport(config: &crate::Config) -> std::sync::Arc<crate::transport::Memory> {
+        let transport = std::sync::Arc::new(crate::transport::Memory::new());
+
+        if config.api_key.is_none() {
+            transport.push(crate::transport::Response::new(200, TOKEN));
+        }
+
+        transport
+    }
+
+    /**
+     * Starts a local server answering each incoming request with the next of `responses` as JSON
+     * body. Every raw request received is sent back through the returned channel.
+     */
+    pub(crate) fn server(
+        responses: &[&str],
+    ) -> (
+        crate::config::Environment,
+        std::sync::mpsc::Receiver<String>,
+    ) {
+        use std::io::{BufRead, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let environment = crate::config::Environment::Custom(format!(
+            "http://{}",
+            listener.local_addr().unwrap()
+        ));
+        let responses = responses.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            for body in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = std::io::BufReader::new(stream);
+                let mut request = String::new();
+                let mut content_length = 0;
+
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+
+                    request.push_str(&line);
+
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                }
+
+                let mut payload = vec![0; content_length];
+                reader.read_exact(&mut payload).unwrap();
+                request.push_str(&String::from_utf8_lossy(&payload));
+                sender.send(request).ok();
+
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+        });
+
+        (environment, receiver)
+    }
+
+    pub(crate) const TOKEN: &str = r#"{
+        "access_token": "access",
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "scope": "payments payment_instruments transactions.history user.subaccounts",
+        "refresh_token": "refresh"
+    }"#;
+
     #[test]
     fn new() -> crate::Result {
         api().map(|_| ())
     }
 
+    #[test]
+    fn environment() -> crate::Result {
+        let (environment, requests) = server(&[TOKEN, "{}"]);
+
+        let config = crate::Config {
+            grant_type: crate::config::GrantType::ClientCredentials,
+            environment,
+
+            ..Default::default()
+        };
+
+        let api = crate::SumUp::from(config)?;
+        assert_eq!(api.access_token().access_token.expose(), "access");
+        assert!(requests.recv().unwrap().starts_with("POST /token "));
+
+        api.checkout().find_by_id("1").ok();
+        assert!(requests.recv().unwrap().starts_with("GET /checkouts/1 "));
+
+        Ok(())
+    }
+
+    #[test]
+    fn renew() -> crate::Result {
+        let transport = std::sync::Arc::new(crate::transport::Memory::new());
+        transport.push(crate::transport::Response::new(200, TOKEN));
+        transport.push(crate::transport::Response::new(200, "[]"));
+
+        let config = crate::Config {
+            refresh_token: Some("configured".into()),
+
+            ..Default::default()
+        };
+        let api = crate::Api::default().with_transport(transport.clone());
+        let sumup = crate::SumUp::with_api(api, config)?;
+
+        assert!(sumup.subaccounts().list()?.is_empty());
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0]
+            .body
+            .as_ref()
+            .unwrap()
+            .contains(r#""refresh_token":"configured""#));
+        assert_eq!(requests[1].header("Authorization"), Some("Bearer access"));
+
+        transport.push(crate::transport::Response::new(
+            401,
+            "{\"message\": \"Expired\"}",
+        ));
+        transport.push(crate::transport::Response::new(
+            200,
+            r#"{"access_token": "renewed", "token_type": "Bearer", "expires_in": 3600, "scope": ""}"#,
+        ));
+        transport.push(crate::transport::Response::new(200, "[]"));
+
+        let clone = sumup.clone();
+        assert!(clone.subaccounts().list()?.is_empty());
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 5);
+        assert!(requests[0].url.ends_with("/token"));
+        assert!(requests[3]
+            .body
+            .as_ref()
+            .unwrap()
+            .contains(r#""refresh_token":"refresh""#));
+        assert_eq!(requests[4].header("Authorization"), Some("Bearer renewed"));
+
+        let access_token = sumup.access_token();
+        assert_eq!(access_token.access_token.expose(), "renewed");
+        assert_eq!(
+            access_token
+                .refresh_token
+                .as_ref()
+                .map(crate::Secret::expose),
+            Some("refresh")
+        );
+        assert!(!access_token.expires_within(crate::EXPIRY_MARGIN));
+
+        transport.push(crate::transport::Response::new(
+            401,
+            "{\"message\": \"Expired\"}",
+        ));
+        transport.push(crate::transport::Response::new(
+            400,
+            "{\"message\": \"Invalid\"}",
+        ));
+        assert!(sumup.subaccounts().list().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn threads() -> crate::Result {
+        fn shared<T: Send + Sync + Clone + 'static>() {}
+        shared::<crate::SumUp>();
+
+        let store = crate::token_store::Memory::new();
+        crate::TokenStore::save(
+            &store,
+            &crate::AccessToken {
+                access_token: "stale".into(),
+                token_type: "Bearer".to_string(),
+                expires_in: Some(0),
+
+                ..Default::default()
+            },
+        )?;
+        let config = crate::Config {
+            grant_type: crate::config::GrantType::ClientCredentials,
+            token_store: Some(std::sync::Arc::new(store)),
+
+            ..Default::default()
+        };
+        let (transport, sumup) = memory_config(config, |api| api)?;
+
+        for _ in 0..4 {
+            transport.push(crate::transport::Response::new(200, "[]"));
+        }
+
+        let threads = (0..4)
+            .map(|_| {
+                let sumup = sumup.clone();
+                std::thread::spawn(move || sumup.subaccounts().list())
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            assert!(thread.join().unwrap()?.is_empty());
+        }
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 5);
+        assert!(requests[1..]
+            .iter()
+            .all(|x| x.header("Authorization") == Some("Bearer access")));
+        assert_eq!(sumup.access_token().access_token.expose(), "access");
+
+        Ok(())
+    }
+
+    #[test]
+    fn poisoned() -> crate::Result {
+        #[derive(Debug, Default)]
+        struct Panic(std::sync::atomic::AtomicUsize);
+
+        impl crate::Interceptor for Panic {
+            fn request(&self, request: &mut crate::transport::Request) -> crate::Result {
+                let calls = self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                assert!(calls != 1, "Panicked renewing {}", request.url);
+
+                Ok(())
+            }
+        }
+
+        let (transport, sumup) = memory_with(|api| api.with_interceptor(Panic::default()))?;
+
+        let clone = sumup.clone();
+        assert!(std::thread::spawn(move || clone.refresh_token(None))
+            .join()
+            .is_err());
+
+        transport.push(crate::transport::Response::new(
+            200,
+            r#"{"access_token": "renewed", "token_type": "Bearer", "expires_in": 3600, "scope": ""}"#,
+        ));
+        sumup.refresh_token(None)?;
+        assert_eq!(sumup.access_token().access_token.expose(), "renewed");
+
+        Ok(())
+    }
+
+    #[test]
+    fn logout() -> crate::Result {
+        use crate::TokenStore as _;
+
+        let store = crate::token_store::Memory::new();
+        let config = crate::Config {
+            grant_type: crate::config::GrantType::ClientCredentials,
+            token_store: Some(std::sync::Arc::new(store.clone())),
+
+            ..Default::default()
+        };
+        let (transport, sumup) = memory_config(config, |api| api)?;
+        transport.push(crate::transport::Response::new(200, ""));
+        transport.push(crate::transport::Response::new(200, ""));
+        sumup.logout()?;
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[1].url, "https://api.sumup.com/revoke");
+        assert!(requests[1]
+            .body
+            .as_ref()
+            .unwrap()
+            .contains(r#""token":"refresh""#));
+        assert!(requests[2]
+            .body
+            .as_ref()
+            .unwrap()
+            .contains(r#""token":"access""#));
+        assert_eq!(store.load()?, None);
+
+        assert!(matches!(
+            sumup.subaccounts().list(),
+            Err(crate::Error::Auth("Logged out"))
+        ));
+        assert_eq!(transport.requests().len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn logout_failure() -> crate::Result {
+        use crate::TokenStore as _;
+
+        let store = crate::token_store::Memory::new();
+        let config = crate::Config {
+            grant_type: crate::config::GrantType::ClientCredentials,
+            token_store: Some(std::sync::Arc::new(store.clone())),
+
+            ..Default::default()
+        };
+        let (transport, sumup) = memory_config(config, |api| api)?;
+        transport.push(crate::transport::Response::new(200, ""));
+        transport.push(crate::transport::Response::new(
+            400,
+            "{\"message\": \"Invalid\"}",
+        ));
+
+        assert!(sumup.logout().is_err());
+        assert_eq!(transport.requests().len(), 3);
+        assert_eq!(store.load()?, None);
+        assert!(matches!(
+            sumup.subaccounts().list(),
+            Err(crate::Error::Auth("Logged out"))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn missing_scope() -> crate::Result {
+        let (transport, sumup) = crate::test::memory()?;
+
+        let error = sumup.merchant().profile().unwrap_err();
+        assert!(matches!(
+            &error,
+            crate::Error::MissingScope(scopes) if **scopes == [crate::config::Scope::UserProfileReadonly]
+        ));
+        assert_eq!(error.to_string(), "Missing scopes: user.profile_readonly");
+        assert_eq!(transport.requests().len(), 1);
+
+        assert!(
+            crate::config::Scope::UserProfile.includes(crate::config::Scope::UserProfileReadonly)
+        );
+        assert!(
+            !crate::config::Scope::UserProfileReadonly.includes(crate::config::Scope::UserProfile)
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn refresh_token() -> crate::Result {
-        let mut api = api()?;
-        let access_token = api.access_token.clone();
+        let api = api()?;
+        let access_token = api.access_token();
 
         api.refresh_token(None)?;
 
-        assert_ne!(access_token, api.access_token);
+        assert_ne!(access_token, api.access_token());
 
         Ok(())
     }
@@ -0,0 +1,128 @@
+/*!
+ * A local HTTP server catching the redirection ending the authorization code flow, for command
+ * line tools. See [`crate::services::Authorization::login`].
+ */
+
+use std::io::{BufRead as _, Write as _};
+
+/**
+ * A server listening on `127.0.0.1`, whose [`Server::redirect_uri`] must be registered as a
+ * redirect URI of the SumUp application.
+ */
+#[derive(Debug)]
+pub struct Server {
+    listener: std::net::TcpListener,
+}
+
+impl Server {
+    /**
+     * Listens on `port`, or on a free port if `0`.
+     */
+    pub fn bind(port: u16) -> crate::Result<Self> {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", port))?;
+
+        Ok(Self { listener })
+    }
+
+    pub fn redirect_uri(&self) -> crate::Result<String> {
+        Ok(format!("http://{}/callback", self.listener.local_addr()?))
+    }
+
+    /**
+     * Waits up to `timeout` for the user to be redirected to [`Server::redirect_uri`], and
+     * returns the authorization code answering `request`.
+     *
+     * Redirections with another state are answered `400 Bad Request` and ignored.
+     */
+    pub fn wait(
+        &self,
+        request: &crate::services::AuthorizationRequest,
+        timeout: std::time::Duration,
+    ) -> crate::Result<String> {
+        let redirect_uri = self.redirect_uri()?;
+        let base = redirect_uri.trim_end_matches("/callback");
+        let deadline = std::time::Instant::now() + timeout;
+        self.listener.set_nonblocking(true)?;
+
+        loop {
+            let stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {
+                    if std::time::Instant::now() >= deadline {
+                        return Err(crate::Error::Auth(
+                            "Timed out waiting for the authorization",
+                        ));
+                    }
+
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                    continue;
+                }
+                Err(error) => return Err(error.into()),
+            };
+            stream.set_nonblocking(false)?;
+            stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+
+            let mut reader = std::io::BufReader::new(stream);
+            let mut line = String::new();
+
+            if reader.read_line(&mut line).is_err() {
+                continue;
+            }
+
+            let target = line.split(' ').nth(1).unwrap_or_default();
+            let mut stream = reader.into_inner();
+
+            if target.split('?').next() != Some("/callback") {
+                write!(
+                    stream,
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .ok();
+                continue;
+            }
+
+            let result = request.code(&format!("{base}{target}"));
+            let (status, message) = match &result {
+                Ok(_) => (
+                    "200 OK",
+                    "You are logged in, you can close this window.".to_string(),
+                ),
+                Err(error) => ("400 Bad Request", format!("Login failed: {error}")),
+            };
+
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{message}",
+                message.len()
+            )
+            .ok();
+
+            if let Err(crate::Error::InvalidState) = result {
+                continue;
+            }
+
+            return result;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn timeout() -> crate::Result {
+        let server = crate::loopback::Server::bind(0)?;
+        let request = crate::services::AuthorizationRequest {
+            url: String::new(),
+            redirect_uri: server.redirect_uri()?,
+            state: "state".into(),
+            code_verifier: None,
+        };
+
+        assert!(matches!(
+            server.wait(&request, std::time::Duration::from_millis(100)),
+            Err(crate::Error::Auth(_))
+        ));
+
+        Ok(())
+    }
+}
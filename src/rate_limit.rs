@@ -0,0 +1,207 @@
+use std::time::{Duration, Instant};
+
+/**
+ * Endpoints sharing a rate limit budget, one per service.
+ */
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Group {
+    Account,
+    Authorization,
+    Checkouts,
+    Customers,
+    Merchant,
+    Payouts,
+    Subaccounts,
+    Transactions,
+}
+
+/**
+ * Allows `requests` requests per `period`, in bursts of up to `requests` requests.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Quota {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    pub fn per_second(requests: u32) -> Self {
+        Self {
+            requests,
+            period: Duration::from_secs(1),
+        }
+    }
+
+    pub fn per_minute(requests: u32) -> Self {
+        Self {
+            requests,
+            period: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    quota: Quota,
+    state: std::sync::Mutex<(f64, Instant)>,
+}
+
+impl Bucket {
+    fn new(quota: Quota) -> crate::Result<Self> {
+        if quota.requests == 0 || quota.period.is_zero() {
+            return Err(crate::Error::InvalidConfig(format!(
+                "rate limit quota: {} requests per {:?}",
+                quota.requests, quota.period
+            )));
+        }
+
+        Ok(Self {
+            quota,
+            state: std::sync::Mutex::new((quota.requests as f64, Instant::now())),
+        })
+    }
+
+    /**
+     * Takes a token, returning how long to wait for it to be available.
+     */
+    fn reserve(&self) -> Duration {
+        let capacity = self.quota.requests as f64;
+        let rate = capacity / self.quota.period.as_secs_f64();
+
+        let tokens = {
+            let mut state = crate::lock(&self.state);
+            let (tokens, last) = &mut *state;
+
+            let now = Instant::now();
+            *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * rate).min(capacity);
+            *last = now;
+            *tokens -= 1.;
+            *tokens
+        };
+
+        if tokens >= 0. {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-tokens / rate)
+        }
+    }
+}
+
+/**
+ * A token bucket rate limiter, shared by every clone of the [`crate::Api`] it is set on.
+ *
+ * Requests of a [`Group`] with its own quota only count against it, the others share the
+ * default quota. Retries count as requests.
+ */
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    default: Option<Bucket>,
+    groups: std::collections::HashMap<Group, Bucket>,
+}
+
+impl RateLimiter {
+    /**
+     * Creates a limiter applying `quota` to every request. A quota of no request, or over no
+     * time, is an [`crate::Error::InvalidConfig`].
+     */
+    pub fn new(quota: Quota) -> crate::Result<Self> {
+        Ok(Self {
+            default: Some(Bucket::new(quota)?),
+            groups: Default::default(),
+        })
+    }
+
+    /**
+     * Applies `quota` to the requests of `group` instead, validated as by [`RateLimiter::new`].
+     */
+    pub fn with_group(mut self, group: Group, quota: Quota) -> crate::Result<Self> {
+        self.groups.insert(group, Bucket::new(quota)?);
+        Ok(self)
+    }
+
+    /**
+     * Takes a token for a request of `group`, returning how long to wait before sending it.
+     */
+    pub(crate) fn reserve(&self, group: Option<Group>) -> Duration {
+        let bucket = group
+            .and_then(|group| self.groups.get(&group))
+            .or(self.default.as_ref());
+
+        match bucket {
+            Some(bucket) => bucket.reserve(),
+            None => Duration::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rate_limit::{Group, Quota, RateLimiter};
+    use std::time::Duration;
+
+    #[test]
+    fn reserve() -> crate::Result {
+        let limiter = RateLimiter::new(Quota::per_second(2))?
+            .with_group(Group::Transactions, Quota::per_minute(1))?;
+
+        assert_eq!(limiter.reserve(None), Duration::ZERO);
+        assert_eq!(limiter.reserve(Some(Group::Checkouts)), Duration::ZERO);
+
+        let delay = limiter.reserve(None);
+        assert!(delay > Duration::from_millis(400) && delay <= Duration::from_millis(500));
+
+        let delay = limiter.reserve(None);
+        assert!(delay > Duration::from_millis(900) && delay <= Duration::from_secs(1));
+
+        assert_eq!(limiter.reserve(Some(Group::Transactions)), Duration::ZERO);
+        assert!(limiter.reserve(Some(Group::Transactions)) > Duration::from_secs(59));
+
+        assert_eq!(RateLimiter::default().reserve(None), Duration::ZERO);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(
+            RateLimiter::new(Quota::per_second(0)),
+            Err(crate::Error::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            RateLimiter::default().with_group(
+                Group::Checkouts,
+                Quota {
+                    requests: 1,
+                    period: Duration::ZERO,
+                },
+            ),
+            Err(crate::Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn shared() -> crate::Result {
+        let limiter = RateLimiter::default().with_group(
+            Group::Subaccounts,
+            Quota {
+                requests: 1,
+                period: Duration::from_millis(50),
+            },
+        )?;
+        let (transport, sumup) = crate::test::memory_with(|api| api.with_rate_limiter(limiter))?;
+        let clone = sumup.clone();
+
+        let start = std::time::Instant::now();
+
+        for _ in 0..3 {
+            transport.push(crate::transport::Response::new(200, "[]"));
+            clone.subaccounts().list()?;
+            transport.push(crate::transport::Response::new(200, "[]"));
+            sumup.subaccounts().list()?;
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(250));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+pub(crate) const MASK: &str = "[REDACTED]";
+
+const SECRETS: &[&str] = &[
+    "access_token",
+    "client_secret",
+    "code",
+    "code_verifier",
+    "cvv",
+    "number",
+    "password",
+    "refresh_token",
+    "token",
+];
+
+const PII: &[&str] = &[
+    "address_line1",
+    "address_line2",
+    "birth_date",
+    "email",
+    "first_name",
+    "last_name",
+    "mobile_phone",
+    "name",
+    "phone",
+    "username",
+];
+
+/**
+ * Masks secrets and personal data in the requests and responses logged at trace level.
+ *
+ * Tokens, client secrets, passwords and card details are always masked, as well as some personal
+ * data fields, to which others can be added. [`Redactor::disabled`] logs everything as is, which
+ * should only be used locally.
+ */
+#[derive(Clone, Debug)]
+pub struct Redactor {
+    fields: Option<std::collections::HashSet<String>>,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        let fields = SECRETS.iter().chain(PII).map(|x| x.to_string()).collect();
+
+        Self {
+            fields: Some(fields),
+        }
+    }
+}
+
+impl Redactor {
+    /**
+     * A redactor logging full requests and responses.
+     */
+    pub fn disabled() -> Self {
+        Self { fields: None }
+    }
+
+    /**
+     * Also masks the values of the JSON fields named `field`, at any depth.
+     */
+    pub fn with_field(mut self, field: &str) -> Self {
+        if let Some(fields) = &mut self.fields {
+            fields.insert(field.to_ascii_lowercase());
+        }
+
+        self
+    }
+
+    pub(crate) fn log_request(&self, request: &crate::transport::Request) {
+        if !log::log_enabled!(log::Level::Trace) {
+            return;
+        }
+
+        log::trace!("-> {} {}", request.method, request.url);
+
+        for (name, value) in &request.headers {
+            log::trace!("-> {name}: {}", self.header(name, value));
+        }
+
+        if let Some(body) = &request.body {
+            log::trace!("-> {}", self.body(body));
+        }
+    }
+
+    pub(crate) fn log_response(&self, response: &crate::transport::Response) {
+        if log::log_enabled!(log::Level::Trace) {
+            log::trace!("<- {} {}", response.status, self.body(&response.body));
+        }
+    }
+
+    fn header<'a>(&self, name: &str, value: &'a str) -> std::borrow::Cow<'a, str> {
+        if self.fields.is_none() || !name.eq_ignore_ascii_case("Authorization") {
+            return value.into();
+        }
+
+        match value.split_once(' ') {
+            Some((scheme, _)) => format!("{scheme} {MASK}").into(),
+            None => MASK.into(),
+        }
+    }
+
+    fn body<'a>(&self, body: &'a str) -> std::borrow::Cow<'a, str> {
+        if self.fields.is_none() {
+            return body.into();
+        }
+
+        match serde_json::from_str::<serde_json::Value>(body) {
+            Ok(mut value) => {
+                self.value(&mut value);
+                value.to_string().into()
+            }
+            Err(_) => body.into(),
+        }
+    }
+
+    fn value(&self, value: &mut serde_json::Value) {
+        let Some(fields) = &self.fields else {
+            return;
+        };
+
+        match value {
+            serde_json::Value::Array(values) => values.iter_mut().for_each(|x| self.value(x)),
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    if value.is_null() {
+                        continue;
+                    }
+
+                    if fields.contains(&key.to_ascii_lowercase()) {
+                        *value = MASK.into();
+                    } else {
+                        self.value(value);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn header() {
+        let redactor = crate::Redactor::default();
+
+        assert_eq!(
+            redactor.header("authorization", "Bearer secret"),
+            "Bearer [REDACTED]"
+        );
+        assert_eq!(redactor.header("Idempotency-Key", "key"), "key");
+        assert_eq!(
+            crate::Redactor::disabled().header("Authorization", "Bearer secret"),
+            "Bearer secret"
+        );
+    }
+
+    #[test]
+    fn body() {
+        let body = r#"{"amount":10,"card":{"cvv":"123","number":"4111111111111111"},"customer":[{"email":"a@example.org","nickname":"a","phone":null}]}"#;
+
+        assert_eq!(
+            crate::Redactor::default().with_field("Nickname").body(body),
+            r#"{"amount":10,"card":{"cvv":"[REDACTED]","number":"[REDACTED]"},"customer":[{"email":"[REDACTED]","nickname":"[REDACTED]","phone":null}]}"#
+        );
+        assert_eq!(crate::Redactor::disabled().body(body), body);
+        assert_eq!(
+            crate::Redactor::default()
+                .body(r#"{"code":"c","code_verifier":"v","grant_type":"authorization_code"}"#),
+            r#"{"code":"[REDACTED]","code_verifier":"[REDACTED]","grant_type":"authorization_code"}"#
+        );
+        assert_eq!(
+            crate::Redactor::default().body("Bad gateway"),
+            "Bad gateway"
+        );
+    }
+
+    #[test]
+    fn revoke() {
+        assert_eq!(
+            crate::Redactor::default()
+                .body(r#"{"client_id":"id","client_secret":"secret","token":"access"}"#),
+            r#"{"client_id":"id","client_secret":"[REDACTED]","token":"[REDACTED]"}"#
+        );
+    }
+}
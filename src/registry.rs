@@ -0,0 +1,278 @@
+/*!
+ * Clients acting on behalf of many merchants, sharing one [`crate::Api`], so its connections and
+ * rate limiter, and authenticated on first use.
+ */
+
+/**
+ * The clients of registered merchants, created from their configuration on first use.
+ */
+#[derive(Debug)]
+pub(crate) struct Merchants<S> {
+    /** The settings common to every merchant, see [`config`] */
+    config: crate::Config,
+    entries: std::sync::Mutex<std::collections::HashMap<String, Entry<S>>>,
+}
+
+#[derive(Debug)]
+enum Entry<S> {
+    Pending(Box<crate::Config>),
+    Ready(S),
+}
+
+impl<S: Clone> Merchants<S> {
+    pub(crate) fn new(config: crate::Config) -> Self {
+        Self {
+            config,
+            entries: Default::default(),
+        }
+    }
+
+    pub(crate) fn insert(&self, merchant_code: &str, refresh_token: crate::Secret) {
+        let config = config(&self.config, refresh_token);
+
+        self.insert_config(merchant_code, config);
+    }
+
+    pub(crate) fn insert_config(&self, merchant_code: &str, config: crate::Config) {
+        crate::lock(&self.entries)
+            .insert(merchant_code.to_string(), Entry::Pending(Box::new(config)));
+    }
+
+    pub(crate) fn remove(&self, merchant_code: &str) -> bool {
+        crate::lock(&self.entries).remove(merchant_code).is_some()
+    }
+
+    pub(crate) fn codes(&self) -> Vec<String> {
+        let mut codes = crate::lock(&self.entries)
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        codes.sort();
+
+        codes
+    }
+
+    /**
+     * Returns the client of the merchant, or else the configuration to create it with.
+     */
+    pub(crate) fn get(&self, merchant_code: &str) -> crate::Result<Result<S, crate::Config>> {
+        match crate::lock(&self.entries).get(merchant_code) {
+            Some(Entry::Ready(client)) => Ok(Ok(client.clone())),
+            Some(Entry::Pending(config)) => Ok(Err(config.as_ref().clone())),
+            None => Err(crate::Error::UnknownMerchant(merchant_code.to_string())),
+        }
+    }
+
+    /**
+     * Keeps the `client` created for the merchant, unless another call already did or the
+     * merchant was removed meanwhile, and returns the one to use.
+     */
+    pub(crate) fn ready(&self, merchant_code: &str, client: S) -> S {
+        let mut entries = crate::lock(&self.entries);
+
+        match entries.get_mut(merchant_code) {
+            Some(Entry::Ready(client)) => client.clone(),
+            Some(entry) => {
+                *entry = Entry::Ready(client.clone());
+                client
+            }
+            None => client,
+        }
+    }
+}
+
+/**
+ * Returns the configuration of a merchant, authenticated with its `refresh_token` and otherwise
+ * like `config`.
+ */
+fn config(config: &crate::Config, refresh_token: crate::Secret) -> crate::Config {
+    crate::Config {
+        code: None,
+        code_verifier: None,
+        access_token: None,
+        refresh_token: Some(refresh_token),
+        api_key: None,
+        token_store: None,
+
+        ..config.clone()
+    }
+}
+
+/**
+ * Returns the settings common to the merchants of an application, checked by
+ * [`crate::Config::validate`]. As merchants authenticate with their refresh token, no grant is
+ * needed.
+ */
+pub(crate) fn credentials(
+    client_id: &str,
+    client_secret: &str,
+    scopes: &[crate::config::Scope],
+    http: crate::config::Http,
+) -> crate::Result<crate::Config> {
+    let config = crate::Config {
+        client_id: client_id.to_string(),
+        client_secret: client_secret.into(),
+        grant_type: crate::config::GrantType::ClientCredentials,
+        scopes: scopes.to_vec().into(),
+        http,
+
+        ..Default::default()
+    };
+    config.validate()?;
+
+    Ok(config)
+}
+
+/**
+ * A [`crate::SumUp`] per merchant, keyed by merchant code. Clones share the merchants.
+ *
+ * ```no_run
+ * # fn main() -> sumup::Result {
+ * let registry = sumup::Registry::with_credentials(
+ *     "id",
+ *     "secret",
+ *     &[sumup::config::Scope::TransactionsHistory],
+ *     Default::default(),
+ * )?;
+ * registry.insert("MXXX", "refresh token");
+ *
+ * let transaction = registry.merchant("MXXX")?.transactions().find_by_id("id")?;
+ * # Ok(())
+ * # }
+ * ```
+ */
+#[derive(Clone, Debug)]
+pub struct Registry {
+    api: crate::Api,
+    merchants: std::sync::Arc<Merchants<crate::SumUp>>,
+}
+
+impl Registry {
+    /**
+     * Creates a registry whose merchants send their requests through `api`, with the client
+     * credentials, scopes and HTTP settings of `config`.
+     */
+    pub fn new(api: crate::Api, config: crate::Config) -> Self {
+        Self {
+            api,
+            merchants: std::sync::Arc::new(Merchants::new(config)),
+        }
+    }
+
+    /**
+     * Creates a registry for the application with `client_id` and `client_secret`, whose
+     * merchants grant `scopes`, sending requests to production with the `http` settings.
+     */
+    pub fn with_credentials(
+        client_id: &str,
+        client_secret: &str,
+        scopes: &[crate::config::Scope],
+        http: crate::config::Http,
+    ) -> crate::Result<Self> {
+        let config = credentials(client_id, client_secret, scopes, http)?;
+
+        Ok(Self::new(crate::Api::from_config(&config)?, config))
+    }
+
+    /**
+     * Registers a merchant authenticated with its `refresh_token`, replacing any previous one.
+     */
+    pub fn insert(&self, merchant_code: &str, refresh_token: impl Into<crate::Secret>) {
+        self.merchants.insert(merchant_code, refresh_token.into());
+    }
+
+    /**
+     * Registers a merchant with its own `config`, to use a token store for example.
+     */
+    pub fn insert_config(&self, merchant_code: &str, config: crate::Config) {
+        self.merchants.insert_config(merchant_code, config);
+    }
+
+    /**
+     * Unregisters a merchant, returning whether it was registered.
+     */
+    pub fn remove(&self, merchant_code: &str) -> bool {
+        self.merchants.remove(merchant_code)
+    }
+
+    /**
+     * Returns the codes of the registered merchants, sorted.
+     */
+    pub fn merchant_codes(&self) -> Vec<String> {
+        self.merchants.codes()
+    }
+
+    /**
+     * Returns the client of a merchant, created on first call.
+     */
+    pub fn merchant(&self, merchant_code: &str) -> crate::Result<crate::SumUp> {
+        match self.merchants.get(merchant_code)? {
+            Ok(sumup) => Ok(sumup),
+            Err(config) => {
+                let sumup = crate::SumUp::with_api(self.api.clone(), config)?;
+
+                Ok(self.merchants.ready(merchant_code, sumup))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn merchant() -> crate::Result {
+        let transport = std::sync::Arc::new(crate::transport::Memory::new());
+        let api = crate::Api::default().with_transport(transport.clone());
+        let config = crate::Config {
+            client_id: "id".to_string(),
+            client_secret: "secret".into(),
+
+            ..Default::default()
+        };
+        let registry = crate::Registry::new(api, config);
+        registry.insert("M2", "refresh 2");
+        registry.insert("M1", "refresh 1");
+        assert_eq!(registry.merchant_codes(), ["M1", "M2"]);
+
+        let sumup = registry.merchant("M1")?;
+        assert!(transport.requests().is_empty());
+
+        transport.push(crate::transport::Response::new(200, crate::test::TOKEN));
+        transport.push(crate::transport::Response::new(200, "[]"));
+        assert!(sumup.subaccounts().list()?.is_empty());
+        assert!(transport.requests()[0]
+            .body
+            .as_ref()
+            .unwrap()
+            .contains(r#""refresh_token":"refresh 1""#));
+
+        transport.push(crate::transport::Response::new(200, "[]"));
+        assert!(registry.merchant("M1")?.subaccounts().list()?.is_empty());
+        assert_eq!(transport.requests().len(), 3);
+
+        assert!(registry.remove("M2"));
+        assert!(matches!(
+            registry.merchant("M2"),
+            Err(crate::Error::UnknownMerchant(code)) if code == "M2"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn with_credentials() -> crate::Result {
+        let scopes = [crate::config::Scope::TransactionsHistory];
+        let registry =
+            crate::Registry::with_credentials("id", "secret", &scopes, Default::default())?;
+        registry.insert("M1", "refresh 1");
+        assert_eq!(registry.merchant_codes(), ["M1"]);
+
+        assert!(matches!(
+            crate::Registry::with_credentials("id", "", &[], Default::default()),
+            Err(crate::Error::InvalidConfig(problems))
+                if problems == "missing client_secret, missing scopes"
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+/**
+ * A decoded response body, with the metadata of the HTTP response it comes from.
+ *
+ * Obtained by calling services through [`crate::Client::with_response`].
+ */
+#[derive(Clone, Debug)]
+pub struct WithMetadata<T> {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /** The body as received, before decoding */
+    pub body: String,
+    pub value: T,
+}
+
+impl<T> WithMetadata<T> {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        crate::transport::find_header(&self.headers, name)
+    }
+
+    /**
+     * The identifier SumUp gives to the request, to quote when contacting its support.
+     */
+    pub fn request_id(&self) -> Option<&str> {
+        self.header("X-Request-Id")
+    }
+
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+impl<T> std::ops::Deref for WithMetadata<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn with_response() -> crate::Result {
+        use crate::Client as _;
+
+        let (transport, sumup) = crate::test::memory()?;
+        transport.push(
+            crate::transport::Response::new(200, "[]")
+                .with_header("x-request-id", "request")
+                .with_header("X-RateLimit-Remaining", "9"),
+        );
+        transport.push(crate::transport::Response::new(201, "{}"));
+
+        let response = sumup.with_response().subaccounts().list()?;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.request_id(), Some("request"));
+        assert_eq!(response.header("x-ratelimit-remaining"), Some("9"));
+        assert_eq!(response.body, "[]");
+        assert!(response.is_empty());
+
+        let response = sumup
+            .with_response()
+            .customer()
+            .create(&crate::Customer::default())?;
+        assert_eq!(response.status, 201);
+        assert_eq!(response.into_value().as_str().len(), 36);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,318 @@
+use std::time::Duration;
+
+/**
+ * How failed requests are replayed.
+ *
+ * A request is retried on connection failures, on `408`, `429`, `500`, `502`, `503` and `504`
+ * responses. By default, only requests safe to replay are retried, see
+ * [`crate::Client::retry_policy`] to override it for a call.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /** The maximum number of attempts, including the first one */
+    pub max_attempts: u32,
+    /** The delay before the first retry, doubled for each following one */
+    pub initial_backoff: Duration,
+    /** The upper bound of the delay between two attempts */
+    pub max_backoff: Duration,
+    /** Randomizes each delay between half and the full backoff */
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /**
+     * A policy never retrying requests.
+     */
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+
+            ..Default::default()
+        }
+    }
+
+    /**
+     * Returns how long to wait before retrying after the `attempt`th try ended with `result`, or
+     * `None` if it shouldn't be retried.
+     *
+     * The `Retry-After` header, in seconds or as an HTTP date, takes precedence over the backoff.
+     * The request isn't retried if it asks to wait longer than `max_backoff`.
+     */
+    pub(crate) fn delay(
+        &self,
+        attempt: u32,
+        result: &crate::Result<crate::transport::Response>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        match result {
+            Ok(response) if is_retryable_status(response.status) => {
+                match response.header("Retry-After").and_then(retry_after) {
+                    Some(delay) if delay > self.max_backoff => None,
+                    Some(delay) => Some(delay),
+                    None => Some(self.backoff(attempt)),
+                }
+            }
+            Ok(_) => None,
+            Err(error) if is_transient(error) => Some(self.backoff(attempt)),
+            Err(_) => None,
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2_u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff);
+
+        if self.jitter {
+            backoff.mul_f64(0.5 + fastrand::f64() / 2.)
+        } else {
+            backoff
+        }
+    }
+}
+
+/**
+ * Parses a `Retry-After` value, either a number of seconds or an HTTP date.
+ */
+fn retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = http_date(value)?;
+
+    Some(
+        date.duration_since(std::time::SystemTime::now())
+            .unwrap_or_default(),
+    )
+}
+
+/**
+ * Parses an HTTP date in the preferred IMF-fixdate format, like `Sun, 06 Nov 1994 08:49:37 GMT`.
+ */
+fn http_date(value: &str) -> Option<std::time::SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let mut parts = value.split_ascii_whitespace();
+    parts.next()?.strip_suffix(',')?;
+    let day: u64 = parts.next()?.parse().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|x| *x == month)? as u64 + 1;
+    let year: u64 = parts.next()?.parse().ok()?;
+    let mut time = parts.next()?.split(':').map(|x| x.parse::<u64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+
+    if parts.next()? != "GMT" || parts.next().is_some() || time.next().is_some() {
+        return None;
+    }
+
+    if !(1..=31).contains(&day) || year < 1970 || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // Days since the epoch of a date of the proleptic Gregorian calendar, in eras of 400 years
+    // starting on March 1st.
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let seconds = days * 86_400 + hour * 3_600 + minute * 60 + second;
+
+    Some(std::time::UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+pub(crate) fn is_retryable_status(status: u16) -> bool {
+    matches!(status, 408 | 429 | 500 | 502 | 503 | 504)
+}
+
+pub(crate) fn is_transient(error: &crate::Error) -> bool {
+    match error {
+        crate::Error::Io(_) => true,
+        crate::Error::Transport(error) => {
+            if let Some(error) = error.downcast_ref::<ureq::Transport>() {
+                return !matches!(
+                    error.kind(),
+                    ureq::ErrorKind::InvalidUrl
+                        | ureq::ErrorKind::UnknownScheme
+                        | ureq::ErrorKind::InvalidProxyUrl
+                );
+            }
+
+            #[cfg(feature = "async")]
+            if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+                return error.is_connect() || error.is_timeout();
+            }
+
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    fn policy() -> crate::RetryPolicy {
+        crate::RetryPolicy {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(3),
+            jitter: false,
+
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn delay() {
+        let policy = policy();
+        let unavailable = Ok(crate::transport::Response::new(503, ""));
+
+        assert_eq!(policy.delay(1, &unavailable), Some(Duration::from_secs(1)));
+        assert_eq!(policy.delay(2, &unavailable), Some(Duration::from_secs(2)));
+        assert_eq!(policy.delay(3, &unavailable), None);
+
+        let policy = crate::RetryPolicy {
+            max_attempts: 5,
+
+            ..policy
+        };
+        assert_eq!(policy.delay(4, &unavailable), Some(Duration::from_secs(3)));
+
+        let too_many = Ok(crate::transport::Response::new(429, "").with_header("Retry-After", "2"));
+        assert_eq!(policy.delay(1, &too_many), Some(Duration::from_secs(2)));
+
+        let too_long =
+            Ok(crate::transport::Response::new(429, "").with_header("Retry-After", "86400"));
+        assert_eq!(policy.delay(1, &too_long), None);
+
+        let past = Ok(crate::transport::Response::new(503, "")
+            .with_header("Retry-After", "Sun, 06 Nov 1994 08:49:37 GMT"));
+        assert_eq!(policy.delay(1, &past), Some(Duration::ZERO));
+
+        let invalid =
+            Ok(crate::transport::Response::new(503, "").with_header("Retry-After", "soon"));
+        assert_eq!(policy.delay(1, &invalid), Some(Duration::from_secs(1)));
+
+        let not_found = Ok(crate::transport::Response::new(404, ""));
+        assert_eq!(policy.delay(1, &not_found), None);
+
+        let reset = Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset).into());
+        assert_eq!(policy.delay(1, &reset), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn http_date() {
+        assert_eq!(
+            super::http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(std::time::UNIX_EPOCH + Duration::from_secs(784_111_777))
+        );
+        assert_eq!(
+            super::http_date("Thu, 01 Jan 1970 00:00:00 GMT"),
+            Some(std::time::UNIX_EPOCH)
+        );
+        assert_eq!(
+            super::http_date("Tue, 29 Feb 2028 23:59:59 GMT"),
+            Some(std::time::UNIX_EPOCH + Duration::from_secs(1_835_481_599))
+        );
+        assert_eq!(super::http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        assert_eq!(super::http_date("Sun, 06 Nov 1994 08:49:37 CET"), None);
+    }
+
+    #[test]
+    fn jitter() {
+        let policy = crate::RetryPolicy {
+            jitter: true,
+
+            ..policy()
+        };
+        let unavailable = Ok(crate::transport::Response::new(503, ""));
+
+        let delay = policy.delay(2, &unavailable).unwrap();
+        assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(2));
+    }
+
+    #[test]
+    fn idempotent() -> crate::Result {
+        use crate::Client as _;
+
+        let (transport, sumup) = crate::test::memory_with(|api| {
+            api.with_retry_policy(crate::RetryPolicy {
+                initial_backoff: Duration::ZERO,
+
+                ..policy()
+            })
+        })?;
+
+        transport.push(crate::transport::Response::new(503, ""));
+        transport.push(crate::transport::Response::new(200, "[]"));
+        assert!(sumup.subaccounts().list()?.is_empty());
+        assert_eq!(transport.requests().len(), 3);
+
+        transport.push(crate::transport::Response::new(
+            503,
+            "{\"message\": \"Unavailable\"}",
+        ));
+        assert!(sumup
+            .subaccounts()
+            .create("sb@example.org", "pass")
+            .is_err());
+        assert_eq!(transport.requests().len(), 4);
+
+        transport.push(crate::transport::Response::new(503, ""));
+        transport.push(crate::transport::Response::new(
+            400,
+            "{\"message\": \"Invalid\"}",
+        ));
+        assert!(sumup
+            .retry_policy(crate::RetryPolicy {
+                max_attempts: 2,
+                initial_backoff: Duration::ZERO,
+
+                ..policy()
+            })
+            .subaccounts()
+            .create("sb@example.org", "pass")
+            .is_err());
+        assert_eq!(transport.requests().len(), 6);
+
+        transport.push(crate::transport::Response::new(
+            503,
+            "{\"message\": \"Unavailable\"}",
+        ));
+        assert!(sumup
+            .retry_policy(crate::RetryPolicy::none())
+            .subaccounts()
+            .list()
+            .is_err());
+        assert_eq!(transport.requests().len(), 7);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+/**
+ * A secret, like a password or a token, printed as `[REDACTED]` by `Debug` and `Display`, and
+ * wiped from memory when dropped. [`Secret::expose`] gives its value.
+ *
+ * It is serialized as is, for token stores.
+ */
+#[derive(Clone, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(secret.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(crate::redact::MASK)
+    }
+}
+
+impl std::fmt::Display for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(crate::redact::MASK)
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.0);
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn redact() {
+        let secret = crate::Secret::new("secret");
+
+        assert_eq!(secret.expose(), "secret");
+        assert_eq!(format!("{secret:?} {secret}"), "[REDACTED] [REDACTED]");
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"secret\"");
+    }
+
+    #[test]
+    fn debug() -> crate::Result {
+        let config = crate::Config::new("id", "client secret", "code");
+        let token: crate::AccessToken = serde_json::from_str(crate::test::TOKEN)?;
+        let debug = format!("{config:?} {token:?}");
+
+        assert!(!debug.contains("client secret"));
+        assert!(!debug.contains("\"access\""));
+        assert!(!debug.contains("\"refresh\""));
+        assert!(debug.contains("access_token: [REDACTED]"));
+        assert!(debug.contains("code: Some([REDACTED])"));
+
+        Ok(())
+    }
+}
@@ -1,19 +1,18 @@
 #[derive(Clone, Debug)]
-pub struct Account<'a> {
-    api: &'a crate::Api,
-    access_token: &'a crate::AccessToken,
+pub struct Account<'a, C = crate::SumUp> {
+    client: &'a C,
 }
 
-impl<'a> Account<'a> {
-    pub fn new(api: &'a crate::Api, access_token: &'a crate::AccessToken) -> Self {
-        Self { api, access_token }
+impl<'a, C: crate::Client> Account<'a, C> {
+    pub fn new(client: &'a C) -> Self {
+        Self { client }
     }
 
     /**
      * <https://developer.sumup.com/docs/api/retrieve-an-account/>
      */
-    pub fn get(&self) -> crate::Result<crate::Account> {
-        self.api.account_get(self.access_token)
+    pub fn get(&self) -> C::Output<'a, crate::Account> {
+        self.client.send(crate::api::account_get())
     }
 }
 
@@ -1,68 +1,239 @@
+/**
+ * A pending authorization code flow. Send the user to `url`, then extract the code from the URL
+ * SumUp redirects them to with [`AuthorizationRequest::code`].
+ *
+ * Keep it, for example in the user session, until the callback.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuthorizationRequest {
+    pub url: String,
+    pub redirect_uri: String,
+    /** The random value SumUp sends back, to protect against CSRF */
+    pub state: crate::Secret,
+    /** The PKCE secret whose challenge was sent, to send again when exchanging the code */
+    pub code_verifier: Option<crate::Secret>,
+}
+
+impl AuthorizationRequest {
+    /**
+     * Validates the `callback` URL SumUp redirected the user to, and returns the authorization
+     * code it contains.
+     */
+    pub fn code(&self, callback: &str) -> crate::Result<String> {
+        let callback = url::Url::parse(callback)
+            .map_err(|_| crate::Error::Auth("Invalid authorization callback URL"))?;
+        let param = |name: &str| {
+            callback
+                .query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+
+        if param("state").as_deref() != Some(self.state.expose()) {
+            return Err(crate::Error::InvalidState);
+        }
+
+        if let Some(error) = param("error") {
+            return Err(crate::Error::OAuth {
+                error,
+                description: param("error_description"),
+            });
+        }
+
+        param("code").ok_or(crate::Error::Auth("Missing authorization code"))
+    }
+}
+
+/**
+ * Returns `N` random bytes, base64url encoded.
+ */
+fn random<const N: usize>() -> crate::Result<String> {
+    use base64::Engine as _;
+
+    Ok(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(crate::random::<N>()?))
+}
+
+fn code_challenge(code_verifier: &str) -> String {
+    use base64::Engine as _;
+
+    let digest = ring::digest::digest(&ring::digest::SHA256, code_verifier.as_bytes());
+
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(digest)
+}
+
 #[derive(Clone, Debug)]
-pub struct Authorization<'a> {
-    api: &'a crate::Api,
+pub struct Authorization<'a, C = crate::Api> {
+    client: &'a C,
     config: &'a crate::Config,
 }
 
-impl<'a> Authorization<'a> {
-    pub fn new(api: &'a crate::Api, config: &'a crate::Config) -> Self {
-        Self { api, config }
+impl<'a, C: crate::Client> Authorization<'a, C> {
+    pub fn new(client: &'a C, config: &'a crate::Config) -> Self {
+        Self { client, config }
     }
 
-    pub fn token(&self) -> crate::Result<crate::AccessToken> {
-        let token = if let Some(access_token) = &self.config.access_token {
-            crate::AccessToken {
-                access_token: access_token.clone(),
+    /**
+     * Returns the token built from the configured API key, else the stored token, else the one
+     * built from the configured access or refresh token.
+     */
+    fn known_token(&self) -> crate::Result<Option<crate::AccessToken>> {
+        if let Some(api_key) = &self.config.api_key {
+            let token = crate::AccessToken {
+                access_token: api_key.clone(),
+                token_type: "Bearer".to_string(),
                 scope: self.config.scopes.clone(),
-                refresh_token: self.config.refresh_token.clone(),
-                expires_in: Some(0),
 
                 ..Default::default()
-            }
-        } else if let Some(refresh_token) = &self.config.refresh_token {
-            crate::AccessToken {
+            };
+
+            return Ok(Some(token));
+        }
+
+        Ok(self.stored_token()?.or_else(|| self.configured_token()))
+    }
+
+    /**
+     * Returns the token built from the configured access or refresh token, if any.
+     */
+    fn configured_token(&self) -> Option<crate::AccessToken> {
+        if let Some(access_token) = &self.config.access_token {
+            let token = crate::AccessToken {
+                access_token: access_token.clone(),
                 scope: self.config.scopes.clone(),
-                refresh_token: Some(refresh_token.clone()),
-                expires_in: Some(0),
+                refresh_token: self.config.refresh_token.clone(),
 
                 ..Default::default()
-            }
+            };
+
+            Some(token)
         } else {
-            use crate::config::GrantType::*;
+            self.config
+                .refresh_token
+                .as_ref()
+                .map(|refresh_token| crate::AccessToken {
+                    scope: self.config.scopes.clone(),
+                    refresh_token: Some(refresh_token.clone()),
+                    expires_in: Some(0),
 
-            match self.config.grant_type {
-                AuthorizationCode => self.token_by_code()?,
-                ClientCredentials => self.token_by_client_credentials()?,
-                Password => self.token_password()?,
-            }
-        };
+                    ..Default::default()
+                })
+        }
+    }
 
-        Ok(token)
+    fn payload(&self) -> crate::Result<serde_json::Value> {
+        use crate::config::GrantType::*;
+
+        match self.config.grant_type {
+            AuthorizationCode => Ok(self.payload_by_code()),
+            ClientCredentials => Ok(self.payload_by_client_credentials()),
+            Password => self.payload_password(),
+        }
     }
 
-    fn token_by_code(&self) -> crate::Result<crate::AccessToken> {
-        let payload = ureq::json!({
+    fn payload_by_code(&self) -> serde_json::Value {
+        self.payload_exchange(
+            self.config.code.as_ref().map(crate::Secret::expose),
+            self.config.redirect_uri.as_deref(),
+            self.config
+                .code_verifier
+                .as_ref()
+                .map(crate::Secret::expose),
+        )
+    }
+
+    fn payload_exchange(
+        &self,
+        code: Option<&str>,
+        redirect_uri: Option<&str>,
+        code_verifier: Option<&str>,
+    ) -> serde_json::Value {
+        let mut payload = ureq::json!({
             "grant_type": "authorization_code",
             "client_id": self.config.client_id,
             "client_secret": self.config.client_secret,
-            "code": self.config.code,
+            "code": code,
         });
 
-        self.api.token(&payload)
+        if let Some(redirect_uri) = redirect_uri {
+            payload["redirect_uri"] = redirect_uri.into();
+        }
+
+        if let Some(code_verifier) = code_verifier {
+            payload["code_verifier"] = code_verifier.into();
+        }
+
+        payload
     }
 
-    fn token_by_client_credentials(&self) -> crate::Result<crate::AccessToken> {
-        let payload = ureq::json!({
+    /**
+     * Starts the authorization code flow, SumUp redirecting the user to `redirect_uri` with the
+     * configured scopes granted. The URL points to the base URL of the client, where the code is
+     * then exchanged.
+     *
+     * <https://developer.sumup.com/docs/authorization#authorization-code-flow>
+     */
+    pub fn authorize(&self, redirect_uri: &str, pkce: bool) -> crate::Result<AuthorizationRequest> {
+        let state = random::<16>()?;
+        let code_verifier = pkce.then(random::<32>).transpose()?;
+
+        let base_url = self.client.base_url().trim_end_matches('/');
+        let mut url = url::Url::parse(&format!("{base_url}/authorize"))
+            .map_err(|error| crate::Error::InvalidConfig(format!("base URL: {error}")))?;
+
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("response_type", "code")
+                .append_pair("client_id", &self.config.client_id)
+                .append_pair("redirect_uri", redirect_uri)
+                .append_pair("state", &state);
+
+            if !self.config.scopes.is_empty() {
+                query.append_pair("scope", &self.config.scopes.to_string());
+            }
+
+            if let Some(code_verifier) = &code_verifier {
+                query
+                    .append_pair("code_challenge", &code_challenge(code_verifier))
+                    .append_pair("code_challenge_method", "S256");
+            }
+        }
+
+        Ok(AuthorizationRequest {
+            url: url.into(),
+            redirect_uri: redirect_uri.to_string(),
+            state: state.into(),
+            code_verifier: code_verifier.map(Into::into),
+        })
+    }
+
+    /**
+     * Requests a token with the `code` answering `request`.
+     */
+    pub fn exchange_code(
+        &self,
+        request: &AuthorizationRequest,
+        code: &str,
+    ) -> C::Output<'a, crate::AccessToken> {
+        let payload = self.payload_exchange(
+            Some(code),
+            Some(&request.redirect_uri),
+            request.code_verifier.as_ref().map(crate::Secret::expose),
+        );
+
+        self.client.send(crate::api::token(payload))
+    }
+
+    fn payload_by_client_credentials(&self) -> serde_json::Value {
+        ureq::json!({
             "grant_type": "client_credentials",
             "client_id": self.config.client_id,
             "client_secret": self.config.client_secret,
             "scope": self.config.scopes,
-        });
-
-        self.api.token(&payload)
+        })
     }
 
-    fn token_password(&self) -> crate::Result<crate::AccessToken> {
+    fn payload_password(&self) -> crate::Result<serde_json::Value> {
         let username = match &self.config.username {
             Some(username) => username,
             None => return Err(crate::Error::Auth("Missing username configuration")),
@@ -82,10 +253,10 @@ impl<'a> Authorization<'a> {
             "password": password,
         });
 
-        self.api.token(&payload)
+        Ok(payload)
     }
 
-    pub(crate) fn refresh_token(&self, refresh_token: &str) -> crate::Result<crate::AccessToken> {
+    pub(crate) fn refresh_token(&self, refresh_token: &str) -> C::Output<'a, crate::AccessToken> {
         let payload = ureq::json!({
             "grant_type": "refresh_token",
             "client_id": self.config.client_id,
@@ -94,6 +265,314 @@ impl<'a> Authorization<'a> {
             "refresh_token": refresh_token,
         });
 
-        self.api.token(&payload)
+        self.client.send(crate::api::token(payload))
+    }
+
+    /**
+     * Invalidates an access or refresh `token`, revoking a refresh token revoking as well the
+     * access tokens it was used for.
+     */
+    pub fn revoke(&self, token: &str) -> C::Output<'a, ()> {
+        let payload = ureq::json!({
+            "client_id": self.config.client_id,
+            "client_secret": self.config.client_secret,
+            "token": token,
+        });
+
+        self.client.send(crate::api::revoke(payload))
+    }
+
+    /**
+     * Returns the token saved in the configured store, if any.
+     */
+    fn stored_token(&self) -> crate::Result<Option<crate::AccessToken>> {
+        match &self.config.token_store {
+            Some(store) => store.load(),
+            None => Ok(None),
+        }
+    }
+
+    /**
+     * Saves `token` to the configured store, if any.
+     */
+    pub(crate) fn save(&self, token: &crate::AccessToken) -> crate::Result {
+        match &self.config.token_store {
+            Some(store) => store.save(token),
+            None => Ok(()),
+        }
+    }
+
+    /**
+     * Removes the token from the configured store, if any.
+     */
+    pub(crate) fn clear(&self) -> crate::Result {
+        match &self.config.token_store {
+            Some(store) => store.clear(),
+            None => Ok(()),
+        }
+    }
+
+    /**
+     * Requests a token to replace `token`, with its refresh token or else as configured. Returns
+     * `None` if the configured access token can't be renewed.
+     */
+    pub(crate) fn renew(
+        &self,
+        token: &crate::AccessToken,
+    ) -> crate::Result<Option<C::Output<'a, crate::AccessToken>>> {
+        if self.config.api_key.is_some() {
+            return Ok(None);
+        }
+
+        if let Some(refresh_token) = &token.refresh_token {
+            return Ok(Some(self.refresh_token(refresh_token.expose())));
+        }
+
+        if self.config.access_token.is_some() || self.config.refresh_token.is_some() {
+            return Ok(None);
+        }
+
+        Ok(Some(self.client.send(crate::api::token(self.payload()?))))
+    }
+}
+
+impl Authorization<'_, crate::Api> {
+    pub fn token(&self) -> crate::Result<crate::AccessToken> {
+        use crate::Client as _;
+
+        if let Some(token) = self.known_token()? {
+            return Ok(token);
+        }
+
+        let token = self.client.send(crate::api::token(self.payload()?))?;
+        self.save(&token)?;
+
+        Ok(token)
+    }
+
+    /**
+     * Runs the authorization code flow with PKCE, catching the redirection with a
+     * [`crate::loopback::Server`] listening on `port` for up to `timeout`. `open` receives the
+     * URL to visit, to print it or launch a browser.
+     *
+     * The token is saved to the configured store.
+     */
+    pub fn login(
+        &self,
+        port: u16,
+        timeout: std::time::Duration,
+        open: impl FnOnce(&str) -> crate::Result,
+    ) -> crate::Result<crate::AccessToken> {
+        let server = crate::loopback::Server::bind(port)?;
+        let request = self.authorize(&server.redirect_uri()?, true)?;
+        open(&request.url)?;
+
+        let code = server.wait(&request, timeout)?;
+        let token = self.exchange_code(&request, &code)?;
+        self.save(&token)?;
+
+        Ok(token)
+    }
+}
+
+#[cfg(feature = "async")]
+impl Authorization<'_, crate::r#async::Api> {
+    pub async fn token(&self) -> crate::Result<crate::AccessToken> {
+        use crate::Client as _;
+
+        if let Some(token) = self.known_token()? {
+            return Ok(token);
+        }
+
+        let token = self.client.send(crate::api::token(self.payload()?)).await?;
+        self.save(&token)?;
+
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn code_challenge() {
+        assert_eq!(
+            super::code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn authorize() -> crate::Result {
+        let transport = std::sync::Arc::new(crate::transport::Memory::new());
+        transport.push(crate::transport::Response::new(200, crate::test::TOKEN));
+
+        let config = crate::Config {
+            client_id: "client".to_string(),
+            scopes: vec![
+                crate::config::Scope::Payments,
+                crate::config::Scope::UserProfile,
+            ]
+            .into(),
+
+            ..Default::default()
+        };
+        let api = crate::Api::default().with_transport(transport.clone());
+        let authorization = crate::services::Authorization::new(&api, &config);
+
+        let request = authorization.authorize("https://example.org/callback", true)?;
+        let url = url::Url::parse(&request.url).unwrap();
+        let params = url.query_pairs().into_owned().collect::<Vec<_>>();
+        let code_challenge =
+            super::code_challenge(request.code_verifier.as_ref().unwrap().expose());
+
+        assert_eq!(url.path(), "/authorize");
+        assert_eq!(
+            params,
+            [
+                ("response_type".to_string(), "code".to_string()),
+                ("client_id".to_string(), "client".to_string()),
+                (
+                    "redirect_uri".to_string(),
+                    "https://example.org/callback".to_string()
+                ),
+                ("state".to_string(), request.state.expose().to_string()),
+                ("scope".to_string(), "payments user.profile".to_string()),
+                ("code_challenge".to_string(), code_challenge),
+                ("code_challenge_method".to_string(), "S256".to_string()),
+            ]
+        );
+
+        let callback = format!(
+            "https://example.org/callback?code=secret&state={}",
+            request.state.expose()
+        );
+        assert_eq!(request.code(&callback)?, "secret");
+        assert!(format!("{request:?}").contains("state: [REDACTED]"));
+        assert!(matches!(
+            request.code("https://example.org/callback?code=secret&state=forged"),
+            Err(crate::Error::InvalidState)
+        ));
+        assert!(matches!(
+            request.code(&format!(
+                "https://example.org/callback?error=access_denied&state={}",
+                request.state.expose()
+            )),
+            Err(crate::Error::OAuth { error, .. }) if error == "access_denied"
+        ));
+
+        let token = authorization.exchange_code(&request, "secret")?;
+        assert_eq!(token.access_token.expose(), "access");
+
+        let payload: serde_json::Value =
+            serde_json::from_str(transport.requests()[0].body.as_ref().unwrap())?;
+        assert_eq!(payload["code"], "secret");
+        assert_eq!(payload["redirect_uri"], "https://example.org/callback");
+        assert_eq!(
+            payload["code_verifier"],
+            request.code_verifier.unwrap().expose()
+        );
+
+        assert!(authorization
+            .authorize("https://example.org/callback", false)?
+            .code_verifier
+            .is_none());
+
+        let api = crate::Api::new("https://example.org/sumup/");
+        let request = crate::services::Authorization::new(&api, &config)
+            .authorize("https://example.org/callback", false)?;
+        assert!(request
+            .url
+            .starts_with("https://example.org/sumup/authorize?"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn api_key() -> crate::Result {
+        let store = crate::token_store::Memory::new();
+        let config = crate::Config {
+            api_key: Some("sup_sk_key".into()),
+            token_store: Some(std::sync::Arc::new(store.clone())),
+
+            ..Default::default()
+        };
+        let (transport, sumup) = crate::test::memory_config(config, |api| {
+            api.with_retry_policy(crate::RetryPolicy::none())
+        })?;
+        transport.push(crate::transport::Response::new(200, "[]"));
+        transport.push(crate::transport::Response::new(
+            401,
+            r#"{"message": "Invalid API key"}"#,
+        ));
+
+        assert!(sumup.subaccounts().list()?.is_empty());
+        assert!(sumup.subaccounts().list().is_err());
+        assert!(sumup.refresh_token(None).is_err());
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].header("Authorization"),
+            Some("Bearer sup_sk_key")
+        );
+        assert_eq!(requests[1].url, "https://api.sumup.com/v0.1/me/accounts");
+        assert!(crate::TokenStore::load(&store)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn login() -> crate::Result {
+        let (environment, requests) = crate::test::server(&[crate::test::TOKEN]);
+        let config = crate::Config {
+            environment,
+
+            ..Default::default()
+        };
+        let api = crate::Api::from_config(&config)?;
+        let authorization = crate::services::Authorization::new(&api, &config);
+
+        let timeout = std::time::Duration::from_secs(10);
+        let token = authorization.login(0, timeout, |url| {
+            let url = url::Url::parse(url).unwrap();
+            let param = |name: &str| {
+                url.query_pairs()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.into_owned())
+                    .unwrap()
+            };
+            let redirect_uri = param("redirect_uri");
+            let state = param("state");
+
+            std::thread::spawn(move || {
+                ureq::get(&format!("{redirect_uri}/../favicon.ico"))
+                    .call()
+                    .ok();
+
+                let forged = ureq::get(&redirect_uri)
+                    .query("code", "forged")
+                    .query("state", "forged")
+                    .call();
+                assert!(matches!(forged, Err(ureq::Error::Status(400, _))));
+
+                let response = ureq::get(&redirect_uri)
+                    .query("code", "secret")
+                    .query("state", &state)
+                    .call()
+                    .unwrap();
+                assert_eq!(response.status(), 200);
+            });
+
+            Ok(())
+        })?;
+
+        assert_eq!(token.access_token.expose(), "access");
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /token "));
+        assert!(request.contains(r#""code":"secret""#));
+        assert!(request.contains(r#""code_verifier":"#));
+
+        Ok(())
     }
 }
@@ -1,12 +1,11 @@
 #[derive(Clone, Debug)]
-pub struct Checkout<'a> {
-    api: &'a crate::Api,
-    access_token: &'a crate::AccessToken,
+pub struct Checkout<'a, C = crate::SumUp> {
+    client: &'a C,
 }
 
-impl<'a> Checkout<'a> {
-    pub fn new(api: &'a crate::Api, access_token: &'a crate::AccessToken) -> Self {
-        Self { api, access_token }
+impl<'a, C: crate::Client> Checkout<'a, C> {
+    pub fn new(client: &'a C) -> Self {
+        Self { client }
     }
 
     /**
@@ -17,43 +16,46 @@ impl<'a> Checkout<'a> {
         merchant_code: &str,
         amount: Option<f32>,
         currency: Option<&str>,
-    ) -> crate::Result<Vec<crate::PaymentMethod>> {
-        self.api
-            .merchants_payment_methods(merchant_code, amount, currency, self.access_token)
+    ) -> C::Output<'a, Vec<crate::PaymentMethod>> {
+        self.client.send(crate::api::merchants_payment_methods(
+            merchant_code,
+            amount,
+            currency,
+        ))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/create-a-checkout/>
      */
-    pub fn create(&self, checkout: &crate::Checkout) -> crate::Result {
-        self.api.checkout_create(checkout, self.access_token)
+    pub fn create(&self, checkout: &crate::Checkout) -> C::Output<'a, crate::IdempotencyKey> {
+        self.client.send(crate::api::checkout_create(checkout))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/list-checkouts/>
      */
-    pub fn list(&self, checkout_reference: &str) -> crate::Result<Vec<crate::Checkout>> {
-        self.api
-            .checkout_list(checkout_reference, self.access_token)
+    pub fn list(&self, checkout_reference: &str) -> C::Output<'a, Vec<crate::Checkout>> {
+        self.client
+            .send(crate::api::checkout_list(checkout_reference))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/retrieve-a-checkout/>
      */
-    pub fn find_by_id(&self, id: &str) -> crate::Result<crate::Checkout> {
-        self.api.checkout_get(id, self.access_token)
+    pub fn find_by_id(&self, id: &str) -> C::Output<'a, crate::Checkout> {
+        self.client.send(crate::api::checkout_get(id))
     }
 
-    pub fn find_by_reference_id(&self, reference_id: &str) -> crate::Result<crate::Checkout> {
-        self.api
-            .checkout_reference_id(reference_id, self.access_token)
+    pub fn find_by_reference_id(&self, reference_id: &str) -> C::Output<'a, crate::Checkout> {
+        self.client
+            .send(crate::api::checkout_reference_id(reference_id))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/deactivate-a-checkout/>
      */
-    pub fn delete(&self, id: &str) -> crate::Result {
-        self.api.checkout_delete(id, self.access_token)
+    pub fn delete(&self, id: &str) -> C::Output<'a, ()> {
+        self.client.send(crate::api::checkout_delete(id))
     }
 
     /**
@@ -65,7 +67,7 @@ impl<'a> Checkout<'a> {
         customer_id: &str,
         card_token: &str,
         installments: Option<u8>,
-    ) -> crate::Result {
+    ) -> C::Output<'a, crate::IdempotencyKey> {
         let payload = ureq::json!({
             "payment_type": "card",
             "customer_id": customer_id,
@@ -73,7 +75,7 @@ impl<'a> Checkout<'a> {
             "installments": installments.unwrap_or(1),
         });
 
-        self.api.checkout_update(id, &payload, self.access_token)
+        self.client.send(crate::api::checkout_update(id, &payload))
     }
 }
 
@@ -1,42 +1,41 @@
 #[derive(Clone, Debug)]
-pub struct Customer<'a> {
-    api: &'a crate::Api,
-    access_token: &'a crate::AccessToken,
+pub struct Customer<'a, C = crate::SumUp> {
+    client: &'a C,
 }
 
-impl<'a> Customer<'a> {
-    pub fn new(api: &'a crate::Api, access_token: &'a crate::AccessToken) -> Self {
-        Self { api, access_token }
+impl<'a, C: crate::Client> Customer<'a, C> {
+    pub fn new(client: &'a C) -> Self {
+        Self { client }
     }
 
     /**
      * <https://developer.sumup.com/docs/api/create-a-customer/>
      */
-    pub fn create(&self, customer: &crate::Customer) -> crate::Result {
-        self.api.customer_create(customer, self.access_token)
+    pub fn create(&self, customer: &crate::Customer) -> C::Output<'a, crate::IdempotencyKey> {
+        self.client.send(crate::api::customer_create(customer))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/update-a-customer/>
      */
-    pub fn update(&self, customer: &crate::Customer) -> crate::Result<crate::Customer> {
-        self.api
-            .customer_update(&customer.customer_id, customer, self.access_token)
+    pub fn update(&self, customer: &crate::Customer) -> C::Output<'a, crate::Customer> {
+        self.client
+            .send(crate::api::customer_update(&customer.customer_id, customer))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/retrieve-a-customer/>
      */
-    pub fn get(&self, id: &str) -> crate::Result<crate::Customer> {
-        self.api.customer_get(id, self.access_token)
+    pub fn get(&self, id: &str) -> C::Output<'a, crate::Customer> {
+        self.client.send(crate::api::customer_get(id))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/list-payment-instruments/>
      */
-    pub fn payment_instruments(&self, customer_id: &str) -> crate::Result<Vec<crate::Card>> {
-        self.api
-            .customer_payment_instruments(customer_id, self.access_token)
+    pub fn payment_instruments(&self, customer_id: &str) -> C::Output<'a, Vec<crate::Card>> {
+        self.client
+            .send(crate::api::customer_payment_instruments(customer_id))
     }
 
     /**
@@ -46,21 +45,31 @@ impl<'a> Customer<'a> {
         &self,
         customer_id: &str,
         card: &crate::Card,
-    ) -> crate::Result<crate::Card> {
+    ) -> C::Output<'a, crate::Card> {
         let payload = ureq::json!({
             "type": "card",
             "card": card,
         });
-        self.api
-            .customer_create_payment_instruments(customer_id, &payload, self.access_token)
+        self.client
+            .send(crate::api::customer_create_payment_instruments(
+                customer_id,
+                &payload,
+            ))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/deactivate-a-payment-instrument/>
      */
-    pub fn delete_payment_instruments(&self, customer_id: &str, card_token: &str) -> crate::Result {
-        self.api
-            .customer_delete_payment_instruments(customer_id, card_token, self.access_token)
+    pub fn delete_payment_instruments(
+        &self,
+        customer_id: &str,
+        card_token: &str,
+    ) -> C::Output<'a, ()> {
+        self.client
+            .send(crate::api::customer_delete_payment_instruments(
+                customer_id,
+                card_token,
+            ))
     }
 }
 
@@ -1,49 +1,49 @@
 #[derive(Clone, Debug)]
-pub struct Merchant<'a> {
-    api: &'a crate::Api,
-    access_token: &'a crate::AccessToken,
+pub struct Merchant<'a, C = crate::SumUp> {
+    client: &'a C,
 }
 
-impl<'a> Merchant<'a> {
-    pub fn new(api: &'a crate::Api, access_token: &'a crate::AccessToken) -> Self {
-        Self { api, access_token }
+impl<'a, C: crate::Client> Merchant<'a, C> {
+    pub fn new(client: &'a C) -> Self {
+        Self { client }
     }
 
-    pub fn profile(&self) -> crate::Result<crate::Profile> {
-        self.api.profile_get(self.access_token)
+    pub fn profile(&self) -> C::Output<'a, crate::Profile> {
+        self.client.send(crate::api::profile_get())
     }
 
-    pub fn update_profile(&self, profile: &crate::Profile) -> crate::Result {
-        self.api.profile_update(profile, self.access_token)
+    pub fn update_profile(&self, profile: &crate::Profile) -> C::Output<'a, ()> {
+        self.client.send(crate::api::profile_update(profile))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/retrieve-dba/>
      */
-    pub fn doing_business_as(&self) -> crate::Result<crate::DoingBusinessAs> {
-        self.api.profile_doing_business_as_get(self.access_token)
+    pub fn doing_business_as(&self) -> C::Output<'a, crate::DoingBusinessAs> {
+        self.client
+            .send(crate::api::profile_doing_business_as_get())
     }
 
     pub fn update_doing_business_as(
         &self,
         dba: &crate::DoingBusinessAs,
-    ) -> crate::Result<crate::DoingBusinessAs> {
-        self.api
-            .profile_doing_business_as_update(dba, self.access_token)
+    ) -> C::Output<'a, crate::DoingBusinessAs> {
+        self.client
+            .send(crate::api::profile_doing_business_as_update(dba))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/list-bank-accounts/>
      */
-    pub fn bank_accounts(&self) -> crate::Result<Vec<crate::BankAccount>> {
-        self.api.profile_bank_accounts(self.access_token)
+    pub fn bank_accounts(&self) -> C::Output<'a, Vec<crate::BankAccount>> {
+        self.client.send(crate::api::profile_bank_accounts())
     }
 
     /**
      * <https://developer.sumup.com/docs/api/list-settings/>
      */
-    pub fn settings(&self) -> crate::Result<crate::Settings> {
-        self.api.profile_settings(self.access_token)
+    pub fn settings(&self) -> C::Output<'a, crate::Settings> {
+        self.client.send(crate::api::profile_settings())
     }
 }
 
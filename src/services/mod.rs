@@ -10,7 +10,7 @@ mod personal;
 mod subaccounts;
 
 pub use account::Account;
-pub use authorization::Authorization;
+pub use authorization::{Authorization, AuthorizationRequest};
 pub use checkout::Checkout;
 pub use customer::Customer;
 pub use merchant::Merchant;
@@ -1,20 +1,19 @@
 #[derive(Clone, Debug)]
-pub struct Payouts<'a> {
-    api: &'a crate::Api,
-    access_token: &'a crate::AccessToken,
+pub struct Payouts<'a, C = crate::SumUp> {
+    client: &'a C,
 }
 
-impl<'a> Payouts<'a> {
-    pub fn new(api: &'a crate::Api, access_token: &'a crate::AccessToken) -> Self {
-        Self { api, access_token }
+impl<'a, C: crate::Client> Payouts<'a, C> {
+    pub fn new(client: &'a C) -> Self {
+        Self { client }
     }
 
-    pub fn payouts(&self, filters: &Filter) -> crate::Result<Vec<crate::Payout>> {
-        self.api.payouts_list(filters, self.access_token)
+    pub fn payouts(&self, filters: &Filter) -> C::Output<'a, Vec<crate::Payout>> {
+        self.client.send(crate::api::payouts_list(filters))
     }
 
-    pub fn transactions(&self, filters: &Filter) -> crate::Result<Vec<crate::Transaction>> {
-        self.api.transactions_list(filters, self.access_token)
+    pub fn transactions(&self, filters: &Filter) -> C::Output<'a, Vec<crate::Transaction>> {
+        self.client.send(crate::api::transactions_list(filters))
     }
 }
 
@@ -26,11 +25,12 @@ pub struct Filter {
     descending_order: bool,
 }
 
-impl ToString for Filter {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let order = if self.descending_order { "desc" } else { "asc" };
 
-        format!(
+        write!(
+            f,
             "start_date={}&end_date={}&limit={}&order={order}&format=json",
             self.start_date,
             self.end_date,
@@ -1,19 +1,18 @@
 #[derive(Clone, Debug)]
-pub struct Personal<'a> {
-    api: &'a crate::Api,
-    access_token: &'a crate::AccessToken,
+pub struct Personal<'a, C = crate::SumUp> {
+    client: &'a C,
 }
 
-impl<'a> Personal<'a> {
-    pub fn new(api: &'a crate::Api, access_token: &'a crate::AccessToken) -> Self {
-        Self { api, access_token }
+impl<'a, C: crate::Client> Personal<'a, C> {
+    pub fn new(client: &'a C) -> Self {
+        Self { client }
     }
 
     /**
      * <https://developer.sumup.com/docs/api/retrieve-a-profile/>
      */
-    pub fn get(&self) -> crate::Result<crate::PersonalProfile> {
-        self.api.personal_get(self.access_token)
+    pub fn get(&self) -> C::Output<'a, crate::PersonalProfile> {
+        self.client.send(crate::api::personal_get())
     }
 }
 
@@ -1,31 +1,30 @@
 #[derive(Clone, Debug)]
-pub struct Subaccounts<'a> {
-    api: &'a crate::Api,
-    access_token: &'a crate::AccessToken,
+pub struct Subaccounts<'a, C = crate::SumUp> {
+    client: &'a C,
 }
 
-impl<'a> Subaccounts<'a> {
-    pub fn new(api: &'a crate::Api, access_token: &'a crate::AccessToken) -> Self {
-        Self { api, access_token }
+impl<'a, C: crate::Client> Subaccounts<'a, C> {
+    pub fn new(client: &'a C) -> Self {
+        Self { client }
     }
 
     /**
      * <https://developer.sumup.com/docs/api/create-a-subaccount/>
      */
-    pub fn create(&self, email: &str, password: &str) -> crate::Result<crate::SubAccount> {
+    pub fn create(&self, email: &str, password: &str) -> C::Output<'a, crate::SubAccount> {
         let payload = ureq::json!({
             "username": email,
             "password": password,
         });
 
-        self.api.subaccounts_create(&payload, self.access_token)
+        self.client.send(crate::api::subaccounts_create(&payload))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/list-subaccounts/>
      */
-    pub fn list(&self) -> crate::Result<Vec<crate::SubAccount>> {
-        self.api.subaccounts_list(self.access_token)
+    pub fn list(&self) -> C::Output<'a, Vec<crate::SubAccount>> {
+        self.client.send(crate::api::subaccounts_list())
     }
 
     /**
@@ -36,7 +35,7 @@ impl<'a> Subaccounts<'a> {
         id: &str,
         new_username: Option<&str>,
         new_password: Option<&str>,
-    ) -> crate::Result<crate::SubAccount> {
+    ) -> C::Output<'a, crate::SubAccount> {
         let mut payload = ureq::json!({});
 
         if let Some(new_username) = new_username {
@@ -47,14 +46,15 @@ impl<'a> Subaccounts<'a> {
             payload["password"] = ureq::json!(new_password);
         }
 
-        self.api.subaccounts_update(id, &payload, self.access_token)
+        self.client
+            .send(crate::api::subaccounts_update(id, &payload))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/deactivate-a-subaccount/>
      */
-    pub fn delete(&self, id: &str) -> crate::Result<crate::SubAccount> {
-        self.api.subaccounts_delete(id, self.access_token)
+    pub fn delete(&self, id: &str) -> C::Output<'a, crate::SubAccount> {
+        self.client.send(crate::api::subaccounts_delete(id))
     }
 }
 
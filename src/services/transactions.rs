@@ -1,63 +1,64 @@
 #[derive(Clone, Debug)]
-pub struct Transactions<'a> {
-    api: &'a crate::Api,
-    access_token: &'a crate::AccessToken,
+pub struct Transactions<'a, C = crate::SumUp> {
+    client: &'a C,
 }
 
-impl<'a> Transactions<'a> {
-    pub fn new(api: &'a crate::Api, access_token: &'a crate::AccessToken) -> Self {
-        Self { api, access_token }
+impl<'a, C: crate::Client> Transactions<'a, C> {
+    pub fn new(client: &'a C) -> Self {
+        Self { client }
     }
 
     /**
      * <https://developer.sumup.com/docs/api/retrieve-a-transaction/>
      */
-    pub fn find_by_id(&self, id: &str) -> crate::Result<crate::Transaction> {
-        self.api.transactions_get(id, self.access_token)
+    pub fn find_by_id(&self, id: &str) -> C::Output<'a, crate::Transaction> {
+        self.client.send(crate::api::transactions_get(id))
     }
 
-    pub fn find_by_internal_id(&self, internal_id: &str) -> crate::Result<crate::Transaction> {
-        self.api
-            .transactions_get_by_internal_id(internal_id, self.access_token)
+    pub fn find_by_internal_id(&self, internal_id: &str) -> C::Output<'a, crate::Transaction> {
+        self.client
+            .send(crate::api::transactions_get_by_internal_id(internal_id))
     }
 
-    pub fn find_by_code(&self, code: &str) -> crate::Result<crate::Transaction> {
-        self.api.transactions_get_by_code(code, self.access_token)
+    pub fn find_by_code(&self, code: &str) -> C::Output<'a, crate::Transaction> {
+        self.client.send(crate::api::transactions_get_by_code(code))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/list-transactions/>
      */
-    pub fn history(&self, filter: &Filter) -> crate::Result<Vec<crate::Transaction>> {
-        self.api.transactions_history(filter, self.access_token)
+    pub fn history(&self, filter: &Filter) -> C::Output<'a, Vec<crate::Transaction>> {
+        self.client.send(crate::api::transactions_history(filter))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/refund-a-transaction/>
      */
-    pub fn full_refund(&self, id: u32) -> crate::Result {
+    pub fn full_refund(&self, id: u32) -> C::Output<'a, crate::IdempotencyKey> {
         let payload = ureq::json!({});
 
-        self.api.transactions_refund(id, payload, self.access_token)
+        self.client
+            .send(crate::api::transactions_refund(id, payload))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/refund-a-transaction/>
      */
-    pub fn refund(&self, id: u32, amount: f32) -> crate::Result {
+    pub fn refund(&self, id: u32, amount: f32) -> C::Output<'a, crate::IdempotencyKey> {
         let payload = ureq::json!({
             "amount": amount,
         });
 
-        self.api.transactions_refund(id, payload, self.access_token)
+        self.client
+            .send(crate::api::transactions_refund(id, payload))
     }
 
     /**
      * <https://developer.sumup.com/docs/api/retrieve-receipt-details/>
      */
-    pub fn receipt(&self, id: u32, merchant_id: u32) -> crate::Result<crate::Receipt> {
-        self.api
-            .transactions_get_receipt(id, merchant_id, self.access_token)
+    pub fn receipt(&self, id: u32, merchant_id: u32) -> C::Output<'a, crate::Receipt> {
+        self.client
+            .send(crate::api::transactions_get_receipt(id, merchant_id))
     }
 }
 
@@ -79,8 +80,8 @@ pub struct Filter {
     pub oldest_ref: Option<String>,
 }
 
-impl ToString for Filter {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut v = Vec::new();
 
         v.push(format!(
@@ -100,15 +101,15 @@ impl ToString for Filter {
         }
 
         for statuse in &self.statuses {
-            v.push(format!("statuse={}", statuse.to_string()));
+            v.push(format!("statuse={statuse}"));
         }
 
         for payment_type in &self.payment_types {
-            v.push(format!("payment_type={}", payment_type.to_string()));
+            v.push(format!("payment_type={payment_type}"));
         }
 
         for ty in &self.types {
-            v.push(format!("type={}", ty.to_string()));
+            v.push(format!("type={ty}"));
         }
 
         if let Some(changes_since) = &self.changes_since {
@@ -133,7 +134,7 @@ impl ToString for Filter {
 
         v.push("format=json".to_string());
 
-        v.join("&")
+        f.write_str(&v.join("&"))
     }
 }
 
@@ -0,0 +1,113 @@
+/*!
+ * The access token shared by a client and its clones, and the steps of renewing and revoking it
+ * which don't send requests, common to the blocking and non-blocking clients.
+ */
+
+#[derive(Clone, Debug)]
+pub(crate) struct Session {
+    access_token: std::sync::Arc<std::sync::Mutex<crate::AccessToken>>,
+}
+
+impl Session {
+    pub(crate) fn new(access_token: crate::AccessToken) -> Self {
+        Self {
+            access_token: std::sync::Arc::new(std::sync::Mutex::new(access_token)),
+        }
+    }
+
+    pub(crate) fn access_token(&self) -> crate::AccessToken {
+        crate::lock(&self.access_token).clone()
+    }
+
+    /**
+     * Returns the access token, or an error after a logout.
+     */
+    pub(crate) fn current(&self) -> crate::Result<crate::AccessToken> {
+        let access_token = self.access_token();
+
+        if access_token.access_token.is_empty() && access_token.expires_in.is_none() {
+            return Err(crate::Error::Auth("Logged out"));
+        }
+
+        Ok(access_token)
+    }
+
+    /**
+     * Returns the access token if another call already replaced the `stale` one.
+     */
+    pub(crate) fn replacement(&self, stale: &crate::AccessToken) -> Option<crate::AccessToken> {
+        let access_token = self.access_token();
+
+        (access_token.access_token != stale.access_token).then_some(access_token)
+    }
+
+    /**
+     * Saves `token`, keeping the refresh token of the `previous` one if it has none, then uses
+     * it for the following requests.
+     */
+    pub(crate) fn replace<C: crate::Client>(
+        &self,
+        authorization: &crate::services::Authorization<'_, C>,
+        previous: &crate::AccessToken,
+        token: crate::AccessToken,
+    ) -> crate::Result<crate::AccessToken> {
+        let token = token.or_refresh_token_of(previous);
+        authorization.save(&token)?;
+        *crate::lock(&self.access_token) = token.clone();
+
+        Ok(token)
+    }
+
+    /**
+     * Returns the tokens to revoke when logging out, the refresh token first, none for an API
+     * key.
+     */
+    pub(crate) fn revocable(&self, config: &crate::Config) -> Vec<crate::Secret> {
+        if config.api_key.is_some() {
+            return Vec::new();
+        }
+
+        let access_token = self.access_token();
+
+        [access_token.refresh_token, Some(access_token.access_token)]
+            .into_iter()
+            .flatten()
+            .filter(|x| !x.is_empty())
+            .collect()
+    }
+
+    /**
+     * Forgets the tokens, unless authenticating with an API key, and removes them from the token
+     * store, whatever `revoked` is. Returns the first error.
+     */
+    pub(crate) fn logout<C: crate::Client>(
+        &self,
+        authorization: &crate::services::Authorization<'_, C>,
+        config: &crate::Config,
+        revoked: crate::Result,
+    ) -> crate::Result {
+        if config.api_key.is_none() {
+            *crate::lock(&self.access_token) = crate::AccessToken::default();
+        }
+
+        let cleared = authorization.clear();
+        log::debug!("Logged out");
+
+        revoked.and(cleared)
+    }
+}
+
+/**
+ * Returns the refresh token to use, the given one or else the one of `access_token`.
+ */
+pub(crate) fn refresh_token<'a>(
+    access_token: &'a crate::AccessToken,
+    refresh_token: Option<&'a str>,
+) -> crate::Result<&'a str> {
+    refresh_token
+        .or(access_token
+            .refresh_token
+            .as_ref()
+            .map(crate::Secret::expose))
+        .ok_or(crate::Error::Auth("There is no refresh token"))
+}
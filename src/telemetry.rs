@@ -0,0 +1,186 @@
+use crate::transport::{Method, Response};
+
+/**
+ * Tracks an API call, with the `tracing` feature in a `sumup.request` span recording its
+ * `endpoint`, `method`, `status`, `latency_ms`, `retries` and `request_id`.
+ */
+pub(crate) struct Call {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "tracing")]
+    start: std::time::Instant,
+}
+
+impl Call {
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn new(method: Method, path: &str) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(
+                "sumup.request",
+                endpoint = path.split('?').next().unwrap_or_default(),
+                method = method.as_str(),
+                status = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+                retries = tracing::field::Empty,
+                request_id = tracing::field::Empty,
+            ),
+            #[cfg(feature = "tracing")]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    /**
+     * Runs `f` inside the span.
+     */
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        let f = || self.span.in_scope(f);
+
+        f()
+    }
+
+    /**
+     * Attaches the span to `future`.
+     */
+    #[cfg(feature = "async")]
+    pub(crate) fn instrument<F: std::future::Future>(
+        &self,
+        future: F,
+    ) -> impl std::future::Future<Output = F::Output> {
+        #[cfg(feature = "tracing")]
+        let future = tracing::Instrument::instrument(future, self.span.clone());
+
+        future
+    }
+
+    /**
+     * Records the `result` of the last of `attempts`.
+     */
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn finish(&self, attempts: u32, result: &crate::Result<Response>) {
+        #[cfg(feature = "tracing")]
+        {
+            let latency = self.start.elapsed().as_millis() as u64;
+            self.span.record("latency_ms", latency);
+            self.span.record("retries", attempts - 1);
+
+            match result {
+                Ok(response) => {
+                    self.span.record("status", response.status);
+
+                    if let Some(request_id) = response.request_id() {
+                        self.span.record("request_id", request_id);
+                    }
+
+                    tracing::debug!(
+                        parent: &self.span,
+                        status = response.status,
+                        latency_ms = latency,
+                        "SumUp API call completed"
+                    );
+                }
+                Err(error) => tracing::warn!(
+                    parent: &self.span,
+                    error = %error,
+                    latency_ms = latency,
+                    "SumUp API call failed"
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Subscriber {
+        spans: Arc<Mutex<Vec<HashMap<String, String>>>>,
+    }
+
+    struct Visitor<'a>(&'a mut HashMap<String, String>);
+
+    impl tracing::field::Visit for Visitor<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(
+                field.name().to_string(),
+                format!("{value:?}").replace('"', ""),
+            );
+        }
+    }
+
+    impl tracing::Subscriber for Subscriber {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut spans = self.spans.lock().unwrap();
+            let mut fields = HashMap::new();
+            span.record(&mut Visitor(&mut fields));
+            spans.push(fields);
+
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+            let mut spans = self.spans.lock().unwrap();
+            values.record(&mut Visitor(&mut spans[span.into_u64() as usize - 1]));
+        }
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, _: &tracing::Event<'_>) {}
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[test]
+    fn span() -> crate::Result {
+        let subscriber = Subscriber::default();
+        let _default = tracing::subscriber::set_default(subscriber.clone());
+
+        let (transport, sumup) = crate::test::memory_with(|api| {
+            api.with_retry_policy(crate::RetryPolicy {
+                initial_backoff: std::time::Duration::ZERO,
+
+                ..Default::default()
+            })
+        })?;
+        transport.push(crate::transport::Response::new(503, ""));
+        transport.push(
+            crate::transport::Response::new(200, "[]").with_header("X-Request-Id", "request"),
+        );
+        sumup.checkout().list("1")?;
+
+        let spans = subscriber.spans.lock().unwrap();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0]["endpoint"], "/token");
+        assert_eq!(spans[1]["endpoint"], "/v0.1/checkouts");
+        assert_eq!(spans[1]["method"], "GET");
+        assert_eq!(spans[1]["status"], "200");
+        assert_eq!(spans[1]["retries"], "1");
+        assert_eq!(spans[1]["request_id"], "request");
+        assert!(spans[1].contains_key("latency_ms"));
+        drop(spans);
+
+        transport.push(crate::transport::Response::new(401, ""));
+        transport.push(crate::transport::Response::new(200, crate::test::TOKEN));
+        transport.push(crate::transport::Response::new(200, "[]"));
+        sumup.checkout().list("1")?;
+
+        let spans = subscriber.spans.lock().unwrap();
+        assert_eq!(spans.len(), 4);
+        assert_eq!(spans[2]["endpoint"], "/v0.1/checkouts");
+        assert_eq!(spans[2]["status"], "200");
+        assert_eq!(spans[2]["retries"], "1");
+        assert_eq!(spans[3]["endpoint"], "/token");
+
+        Ok(())
+    }
+}
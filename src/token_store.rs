@@ -0,0 +1,180 @@
+/*!
+ * Persistence of access tokens, so a process can resume with the token of its last run.
+ *
+ * Set [`crate::Config::token_store`] to load the token from a store on startup, instead of
+ * requesting a new one, and to save it after every renewal.
+ */
+
+/**
+ * Where an access token is loaded from and saved to.
+ */
+pub trait TokenStore: std::fmt::Debug + Send + Sync {
+    /**
+     * Returns the saved token, `None` if there isn't any.
+     */
+    fn load(&self) -> crate::Result<Option<crate::AccessToken>>;
+
+    fn save(&self, token: &crate::AccessToken) -> crate::Result;
+
+    /**
+     * Removes the saved token, if any.
+     */
+    fn clear(&self) -> crate::Result;
+}
+
+/**
+ * A store keeping the token in memory, shared by its clones.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Memory {
+    token: std::sync::Arc<std::sync::Mutex<Option<crate::AccessToken>>>,
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TokenStore for Memory {
+    fn load(&self) -> crate::Result<Option<crate::AccessToken>> {
+        Ok(crate::lock(&self.token).clone())
+    }
+
+    fn save(&self, token: &crate::AccessToken) -> crate::Result {
+        *crate::lock(&self.token) = Some(token.clone());
+
+        Ok(())
+    }
+
+    fn clear(&self) -> crate::Result {
+        *crate::lock(&self.token) = None;
+
+        Ok(())
+    }
+}
+
+/**
+ * A store saving the token as JSON in a file, only readable by its owner on unix.
+ */
+#[derive(Clone, Debug)]
+pub struct File {
+    path: std::path::PathBuf,
+}
+
+impl File {
+    pub fn new(path: impl Into<std::path::PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
+
+impl TokenStore for File {
+    fn load(&self) -> crate::Result<Option<crate::AccessToken>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /**
+     * Writes the token to a temporary file then renames it, so a crash never leaves a
+     * truncated token behind.
+     */
+    fn save(&self, token: &crate::AccessToken) -> crate::Result {
+        use std::io::Write as _;
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options.open(&tmp)?;
+        file.write_all(serde_json::to_string(token)?.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, &self.path)?;
+
+        Ok(())
+    }
+
+    fn clear(&self) -> crate::Result {
+        match std::fs::remove_file(&self.path) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::token_store::TokenStore as _;
+
+    #[test]
+    fn file() -> crate::Result {
+        let path =
+            std::env::temp_dir().join(format!("sumup-{}.json", crate::IdempotencyKey::new()?));
+        let store = crate::token_store::File::new(&path);
+        assert_eq!(store.load()?, None);
+
+        let token: crate::AccessToken = serde_json::from_str(crate::test::TOKEN)?;
+        store.save(&token)?;
+        assert_eq!(store.load()?, Some(token));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+
+            assert_eq!(
+                std::fs::metadata(&path)?.permissions().mode() & 0o777,
+                0o600
+            );
+        }
+
+        store.clear()?;
+        assert!(!path.exists());
+        assert_eq!(store.load()?, None);
+        store.clear()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn sumup() -> crate::Result {
+        let store = crate::token_store::Memory::new();
+        let config = crate::Config {
+            grant_type: crate::config::GrantType::ClientCredentials,
+            token_store: Some(std::sync::Arc::new(store.clone())),
+
+            ..Default::default()
+        };
+
+        let (transport, sumup) = crate::test::memory_config(config.clone(), |api| api)?;
+        assert_eq!(store.load()?.unwrap().access_token.expose(), "access");
+
+        let sumup = crate::SumUp::with_api(sumup.api, config)?;
+        assert_eq!(transport.requests().len(), 1);
+
+        transport.push(crate::transport::Response::new(
+            200,
+            r#"{"access_token": "renewed", "token_type": "Bearer", "expires_in": 3600, "scope": ""}"#,
+        ));
+        sumup.refresh_token(None)?;
+
+        let token = store.load()?.unwrap();
+        assert_eq!(token.access_token.expose(), "renewed");
+        assert_eq!(
+            token.refresh_token.as_ref().map(crate::Secret::expose),
+            Some("refresh")
+        );
+
+        Ok(())
+    }
+}
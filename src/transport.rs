@@ -0,0 +1,438 @@
+/*!
+ * HTTP layer used by [`crate::Api`] to reach the SumUp API.
+ */
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Method {
+    Delete,
+    Get,
+    Post,
+    Put,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Delete => "DELETE",
+            Self::Get => "GET",
+            Self::Post => "POST",
+            Self::Put => "PUT",
+        }
+    }
+}
+
+impl std::fmt::Display for Method {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Request {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /**
+     * Replaces the value of the `name` header, or adds it.
+     */
+    pub(crate) fn set_header(&mut self, name: &str, value: String) {
+        match self
+            .headers
+            .iter_mut()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        {
+            Some((_, old)) => *old = value,
+            None => self.headers.push((name.to_string(), value)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn new(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /**
+     * The identifier SumUp gives to the request, to quote when contacting its support.
+     */
+    pub fn request_id(&self) -> Option<&str> {
+        self.header("X-Request-Id")
+    }
+
+    /**
+     * Returns the API error contained in the body for a 4xx or 5xx status.
+     */
+    pub(crate) fn check(&self) -> crate::Result {
+        if self.status >= 400 {
+            return Err(crate::Error::status(self.status, &self.body));
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Decodes the body, or the API error it contains for a 4xx or 5xx status.
+     */
+    pub(crate) fn json<T: serde::de::DeserializeOwned>(&self) -> crate::Result<T> {
+        self.check()?;
+
+        serde_json::from_str(&self.body).map_err(crate::Error::from)
+    }
+}
+
+pub(crate) fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+pub trait Transport: std::fmt::Debug + Send + Sync {
+    /**
+     * Sends `request` and returns the response, whatever its status.
+     */
+    fn send(&self, request: Request) -> crate::Result<Response>;
+}
+
+impl<T: Transport + ?Sized> Transport for std::sync::Arc<T> {
+    fn send(&self, request: Request) -> crate::Result<Response> {
+        (**self).send(request)
+    }
+}
+
+/**
+ * The default transport, based on [`ureq`].
+ */
+#[derive(Clone, Debug)]
+pub struct Ureq {
+    agent: ureq::Agent,
+}
+
+impl Ureq {
+    pub fn new(agent: ureq::Agent) -> Self {
+        Self { agent }
+    }
+
+    /**
+     * Builds an agent, whose connections are reused by every request, according to `http`.
+     */
+    pub fn with_config(http: &crate::config::Http) -> crate::Result<Self> {
+        let mut builder = ureq::AgentBuilder::new();
+
+        if let Some(timeout) = http.connect_timeout {
+            builder = builder.timeout_connect(timeout);
+        }
+
+        if let Some(timeout) = http.read_timeout {
+            builder = builder.timeout_read(timeout);
+        }
+
+        if let Some(timeout) = http.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        if let Some(proxy) = &http.proxy {
+            let proxy = ureq::Proxy::new(proxy)
+                .map_err(|error| crate::Error::InvalidConfig(format!("proxy: {error}")))?;
+            builder = builder.proxy(proxy);
+        }
+
+        if !http.root_certificates.is_empty() {
+            builder = builder.tls_config(std::sync::Arc::new(tls_config(&http.root_certificates)?));
+        }
+
+        Ok(Self::new(builder.build()))
+    }
+}
+
+fn tls_config(root_certificates: &[String]) -> crate::Result<rustls::ClientConfig> {
+    use rustls::pki_types::{pem::PemObject, CertificateDer};
+
+    let invalid = |error: &dyn std::fmt::Display| {
+        crate::Error::InvalidConfig(format!("root certificates: {error}"))
+    };
+
+    let mut roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+
+    for pem in root_certificates {
+        let mut found = false;
+
+        for certificate in CertificateDer::pem_slice_iter(pem.as_bytes()) {
+            roots
+                .add(certificate.map_err(|error| invalid(&error))?)
+                .map_err(|error| invalid(&error))?;
+            found = true;
+        }
+
+        if !found {
+            return Err(invalid(&"no PEM certificate found"));
+        }
+    }
+
+    let provider = std::sync::Arc::new(rustls::crypto::ring::default_provider());
+    let config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|error| invalid(&error))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    Ok(config)
+}
+
+impl Default for Ureq {
+    fn default() -> Self {
+        Self::new(ureq::Agent::new())
+    }
+}
+
+impl Transport for Ureq {
+    fn send(&self, request: Request) -> crate::Result<Response> {
+        let mut call = self.agent.request(request.method.as_str(), &request.url);
+
+        for (name, value) in &request.headers {
+            call = call.set(name, value);
+        }
+
+        let result = match &request.body {
+            Some(body) => call.send_string(body),
+            None => call.call(),
+        };
+
+        let response = match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(error) => return Err(error.into()),
+        };
+
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = response.header(&name)?.to_string();
+                Some((name, value))
+            })
+            .collect();
+
+        Ok(Response {
+            status: response.status(),
+            headers,
+            body: response
+                .into_string()
+                .map_err(|error| crate::Error::Transport(Box::new(error)))?,
+        })
+    }
+}
+
+/**
+ * A transport answering queued responses without any network access, and recording the requests
+ * it receives. Useful to test code using this crate.
+ */
+#[derive(Debug, Default)]
+pub struct Memory {
+    requests: std::sync::Mutex<Vec<Request>>,
+    responses: std::sync::Mutex<std::collections::VecDeque<Response>>,
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Queues a response for a future request.
+     */
+    pub fn push(&self, response: Response) {
+        crate::lock(&self.responses).push_back(response);
+    }
+
+    /**
+     * Returns the requests received so far.
+     */
+    pub fn requests(&self) -> Vec<Request> {
+        crate::lock(&self.requests).clone()
+    }
+}
+
+impl Transport for Memory {
+    fn send(&self, request: Request) -> crate::Result<Response> {
+        let error = std::io::Error::new(
+            std::io::ErrorKind::NotConnected,
+            format!("No response queued for {} {}", request.method, request.url),
+        );
+
+        crate::lock(&self.requests).push(request);
+
+        crate::lock(&self.responses)
+            .pop_front()
+            .ok_or_else(|| error.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn memory() -> crate::Result {
+        let (transport, sumup) = crate::test::memory()?;
+        transport.push(crate::transport::Response::new(
+            404,
+            r#"{"message": "Not found", "error_code": "NOT_FOUND"}"#,
+        ));
+
+        assert!(matches!(
+            sumup.checkout().find_by_id("1"),
+            Err(crate::Error::Api(_))
+        ));
+        assert!(sumup.checkout().find_by_id("1").is_err());
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].url, "https://api.sumup.com/token");
+        assert_eq!(requests[0].header("content-type"), Some("application/json"));
+        assert_eq!(requests[1].method, crate::transport::Method::Get);
+        assert_eq!(requests[1].header("Authorization"), Some("Bearer access"));
+
+        Ok(())
+    }
+
+    const CA: &str = "-----BEGIN CERTIFICATE-----
+MIIBezCCASGgAwIBAgIURdSDjVIUqv3IV3PW/ea3tLoTnmAwCgYIKoZIzj0EAwIw
+EjEQMA4GA1UEAwwHVGVzdCBDQTAgFw0yNjEwMTgxMjAxMTNaGA8yMTI2MDkyNDEy
+MDExM1owEjEQMA4GA1UEAwwHVGVzdCBDQTBZMBMGByqGSM49AgEGCCqGSM49AwEH
+A0IABJTgZzizRlXUcy9DQ0QsUsKZm2ral80/0HsT7OEHIIfb+JEIUrMS2LqzlZsd
+K1Bmy6VY1zqYwhDry/WFQNIDc3SjUzBRMB0GA1UdDgQWBBS2XUUNshXSArVYFCWN
+mSno37qnPjAfBgNVHSMEGDAWgBS2XUUNshXSArVYFCWNmSno37qnPjAPBgNVHRMB
+Af8EBTADAQH/MAoGCCqGSM49BAMCA0gAMEUCIFSzlSP4KYV4HsDzyNdpnl6IgvSV
+2/O3q+WjIo874xqtAiEA2kis1Wi4e1pEZzLK/P5s9rkwPdN3HqPd+22Nj9QTtc8=
+-----END CERTIFICATE-----";
+
+    #[test]
+    fn proxy() -> crate::Result {
+        let (proxy, requests) = crate::test::server(&[crate::test::TOKEN]);
+
+        let config = crate::Config {
+            grant_type: crate::config::GrantType::ClientCredentials,
+            environment: crate::config::Environment::Custom("http://sumup.test".to_string()),
+            http: crate::config::Http {
+                proxy: Some(proxy.url().to_string()),
+                root_certificates: vec![CA.to_string()],
+
+                ..Default::default()
+            },
+
+            ..Default::default()
+        };
+
+        crate::SumUp::from(config)?;
+        assert!(requests
+            .recv()
+            .unwrap()
+            .starts_with("POST http://sumup.test/token "));
+
+        Ok(())
+    }
+
+    #[test]
+    fn timeout() -> crate::Result {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+
+        let config = crate::Config {
+            grant_type: crate::config::GrantType::ClientCredentials,
+            environment: crate::config::Environment::Custom(format!(
+                "http://{}",
+                listener.local_addr()?
+            )),
+            http: crate::config::Http {
+                read_timeout: Some(std::time::Duration::from_millis(50)),
+
+                ..Default::default()
+            },
+
+            ..Default::default()
+        };
+        let api = crate::Api::from_config(&config)?.with_retry_policy(crate::RetryPolicy::none());
+
+        assert!(matches!(
+            crate::SumUp::with_api(api, config),
+            Err(crate::Error::Transport(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_config() {
+        let http = crate::config::Http {
+            root_certificates: vec!["certificate".to_string()],
+
+            ..Default::default()
+        };
+        assert!(matches!(
+            crate::transport::Ureq::with_config(&http),
+            Err(crate::Error::InvalidConfig(_))
+        ));
+
+        let http = crate::config::Http {
+            proxy: Some("ftp://proxy".to_string()),
+
+            ..Default::default()
+        };
+        assert!(matches!(
+            crate::transport::Ureq::with_config(&http),
+            Err(crate::Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn errors() -> crate::Result {
+        use crate::transport::Transport as _;
+
+        let response = crate::transport::Response::new(502, "<h1>Bad gateway</h1>");
+        assert!(matches!(
+            response.check(),
+            Err(crate::Error::Http { status: 502, body }) if body == "<h1>Bad gateway</h1>"
+        ));
+
+        let address = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        let request = crate::transport::Request {
+            method: crate::transport::Method::Get,
+            url: format!("http://{address}/"),
+            headers: Vec::new(),
+            body: None,
+        };
+        assert!(matches!(
+            crate::transport::Ureq::default().send(request),
+            Err(crate::Error::Transport(_))
+        ));
+
+        Ok(())
+    }
+}